@@ -0,0 +1,515 @@
+//! Event-sourced aggregates, loaded from and saved to their own stream.
+//!
+//! ```no_run
+//! # use eventstore::{Aggregate, Client, Repository};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Default)]
+//! struct Account {
+//!     balance: i64,
+//! }
+//!
+//! #[derive(Clone, Serialize, Deserialize)]
+//! enum AccountEvent {
+//!     Deposited(i64),
+//! }
+//!
+//! impl Aggregate for Account {
+//!     type Event = AccountEvent;
+//!     type Command = i64;
+//!     type Error = std::convert::Infallible;
+//!
+//!     fn aggregate_type() -> &'static str {
+//!         "account"
+//!     }
+//!
+//!     fn event_type(_: &AccountEvent) -> String {
+//!         "deposited".to_string()
+//!     }
+//!
+//!     fn apply(&mut self, event: AccountEvent) {
+//!         match event {
+//!             AccountEvent::Deposited(amount) => self.balance += amount,
+//!         }
+//!     }
+//!
+//!     fn handle(&self, amount: i64) -> Result<Vec<AccountEvent>, Self::Error> {
+//!         Ok(vec![AccountEvent::Deposited(amount)])
+//!     }
+//! }
+//!
+//! # async fn doc(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let repository = Repository::<Account>::new(client);
+//! let mut account = repository.load("42").await?;
+//!
+//! account.execute(100)?;
+//! repository.save(&mut account).await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::TryStreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::codec::{Codec, CodecError, CodecSet, Json, JSON_CONTENT_TYPE};
+use crate::{
+    All, AppendToStreamOptions, Client, CurrentRevision, EventData, ExpectedRevision, ReadResult,
+    ReadStreamOptions, RecordedEvent, Single, StreamPosition, UpdateStreamMetadataOptions,
+    WriteResult,
+};
+
+/// A consistency boundary which state is the result of folding its events.
+pub trait Aggregate: Default + Send + Sync {
+    type Event: Clone + Send + Sync;
+    type Command;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Category of the aggregate. An aggregate with the id `id` is stored in the stream
+    /// `{aggregate_type}-{id}`.
+    fn aggregate_type() -> &'static str;
+
+    /// Returns the event type under which the event is stored.
+    fn event_type(event: &Self::Event) -> String;
+
+    /// Updates the aggregate state with an event.
+    fn apply(&mut self, event: Self::Event);
+
+    /// Decides which events a command produces, given the current state.
+    fn handle(&self, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error>;
+
+    /// Name of the stream holding the aggregate events.
+    fn stream_name(id: &str) -> String {
+        format!("{}-{}", Self::aggregate_type(), id)
+    }
+
+    /// Name of the companion stream holding the aggregate snapshots,
+    /// `{aggregate_type}_snapshot-{id}`. It belongs to the `{aggregate_type}_snapshot` category,
+    /// so snapshots don't show up among the aggregate events in `$ce-{aggregate_type}`.
+    fn snapshot_stream_name(id: &str) -> String {
+        format!("{}_snapshot-{}", Self::aggregate_type(), id)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RepositoryError<E> {
+    #[error(
+        "Concurrency conflict on stream {stream_name}: expected {expected:?}, got {current:?}"
+    )]
+    Conflict {
+        stream_name: String,
+        expected: ExpectedRevision,
+        current: CurrentRevision,
+    },
+    #[error("Command rejected: {0}")]
+    Rejected(E),
+    #[error("Stream {0} is deleted")]
+    StreamDeleted(String),
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+/// An aggregate loaded from its stream, along with the events it produced since.
+pub struct AggregateRoot<A: Aggregate> {
+    id: String,
+    revision: Option<u64>,
+    snapshot_revision: Option<u64>,
+    state: A,
+    pending: Vec<A::Event>,
+}
+
+impl<A: Aggregate> AggregateRoot<A> {
+    pub(crate) fn new(id: String, revision: Option<u64>, state: A) -> Self {
+        Self {
+            id,
+            revision,
+            snapshot_revision: None,
+            state,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Revision of the last event folded from the stream. `None` if the stream doesn't exist.
+    pub fn revision(&self) -> Option<u64> {
+        self.revision
+    }
+
+    pub fn state(&self) -> &A {
+        &self.state
+    }
+
+    /// Revision of the aggregate stream covered by the latest known snapshot.
+    pub fn snapshot_revision(&self) -> Option<u64> {
+        self.snapshot_revision
+    }
+
+    /// Events produced since the aggregate was loaded or last saved.
+    pub fn pending_events(&self) -> &[A::Event] {
+        &self.pending
+    }
+
+    /// Runs a command against the current state and applies the resulting events. Those events
+    /// are only persisted when calling [`Repository::save`].
+    pub fn execute(&mut self, command: A::Command) -> Result<(), A::Error> {
+        for event in self.state.handle(command)? {
+            self.state.apply(event.clone());
+            self.pending.push(event);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn expected_revision(&self) -> ExpectedRevision {
+        match self.revision {
+            Some(revision) => ExpectedRevision::Exact(revision),
+            None => ExpectedRevision::NoStream,
+        }
+    }
+}
+
+/// Configures when aggregates are snapshotted.
+#[derive(Clone, Debug)]
+pub struct SnapshotOptions {
+    pub(crate) every: u64,
+    pub(crate) max_count: u64,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            every: 100,
+            max_count: 1,
+        }
+    }
+}
+
+impl SnapshotOptions {
+    /// Takes a snapshot once that many events were appended since the latest snapshot.
+    /// Default: 100.
+    pub fn every(self, every: u64) -> Self {
+        Self {
+            every: every.max(1),
+            ..self
+        }
+    }
+
+    /// Number of snapshots kept in the companion stream, enforced through its `max_count`
+    /// metadata. Default: 1.
+    pub fn max_count(self, max_count: u64) -> Self {
+        Self {
+            max_count: max_count.max(1),
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SnapshotRef<'a, S> {
+    revision: u64,
+    state: &'a S,
+}
+
+#[derive(Deserialize)]
+struct SnapshotOwned<S> {
+    revision: u64,
+    state: S,
+}
+
+type EncodeSnapshot<A> = dyn Fn(&A, u64) -> Result<EventData, CodecError> + Send + Sync;
+type DecodeSnapshot<A> = dyn Fn(&RecordedEvent) -> Result<(A, u64), CodecError> + Send + Sync;
+
+struct Snapshots<A> {
+    options: SnapshotOptions,
+    encode: Box<EncodeSnapshot<A>>,
+    decode: Box<DecodeSnapshot<A>>,
+}
+
+/// Loads and saves aggregates of type `A`, encoding their events with `C`.
+pub struct Repository<A, C = Json> {
+    client: Client,
+    codec: C,
+    read_options: ReadStreamOptions,
+    append_options: AppendToStreamOptions,
+    snapshots: Option<Snapshots<A>>,
+}
+
+impl<A: Aggregate> Repository<A> {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            codec: Json,
+            read_options: Default::default(),
+            append_options: Default::default(),
+            snapshots: None,
+        }
+    }
+}
+
+impl<A, C> Repository<A, C>
+where
+    A: Aggregate,
+    C: Codec<A::Event> + CodecSet<A::Event>,
+{
+    /// Uses a different codec to encode and decode events.
+    pub fn with_codec<D>(self, codec: D) -> Repository<A, D>
+    where
+        D: Codec<A::Event> + CodecSet<A::Event>,
+    {
+        Repository {
+            client: self.client,
+            codec,
+            read_options: self.read_options,
+            append_options: self.append_options,
+            snapshots: self.snapshots,
+        }
+    }
+
+    /// Stores JSON snapshots of the aggregate state in its companion stream, so loading it
+    /// doesn't require reading its whole stream.
+    pub fn with_snapshots(self, options: SnapshotOptions) -> Self
+    where
+        A: Serialize + DeserializeOwned,
+    {
+        let snapshots = Snapshots {
+            options,
+            encode: Box::new(|state: &A, revision| {
+                EventData::json("snapshot", SnapshotRef { revision, state })
+                    .map_err(|e| CodecError::encode(JSON_CONTENT_TYPE, e))
+            }),
+            decode: Box::new(|event: &RecordedEvent| {
+                let snapshot = event
+                    .as_json::<SnapshotOwned<A>>()
+                    .map_err(|e| CodecError::decode(JSON_CONTENT_TYPE, e))?;
+
+                Ok((snapshot.state, snapshot.revision))
+            }),
+        };
+
+        Self {
+            snapshots: Some(snapshots),
+            ..self
+        }
+    }
+
+    /// Options used when reading aggregate streams.
+    pub fn read_options(self, read_options: ReadStreamOptions) -> Self {
+        Self {
+            read_options,
+            ..self
+        }
+    }
+
+    /// Options used when appending to aggregate streams. The expected revision is always
+    /// overridden by the repository.
+    pub fn append_options(self, append_options: AppendToStreamOptions) -> Self {
+        Self {
+            append_options,
+            ..self
+        }
+    }
+
+    /// Loads an aggregate by folding its stream, starting from its latest snapshot if
+    /// snapshots are enabled. The snapshot is ignored when the aggregate stream doesn't exist,
+    /// like after it was soft-deleted.
+    pub async fn load(
+        &self,
+        id: impl AsRef<str>,
+    ) -> Result<AggregateRoot<A>, RepositoryError<A::Error>> {
+        let id = id.as_ref().to_string();
+        let stream_name = A::stream_name(id.as_str());
+        let mut state = A::default();
+        let mut revision = None;
+        let mut read_options = self.read_options.clone().forwards();
+
+        let snapshot = self.load_snapshot(id.as_str()).await?;
+        let snapshot_revision = snapshot.as_ref().map(|(_, revision)| *revision);
+
+        if let Some((snapshot, snapshot_revision)) = snapshot {
+            state = snapshot;
+            revision = Some(snapshot_revision);
+            read_options = read_options
+                .position(StreamPosition::Position(snapshot_revision + 1))
+                .forwards();
+        }
+
+        match self
+            .client
+            .read_stream(stream_name.as_str(), &read_options, All)
+            .await?
+        {
+            // Without events there's nothing to fold, a snapshot that outlived its stream is ignored.
+            ReadResult::StreamNotFound(_) => {
+                return Ok(AggregateRoot::new(id, None, A::default()));
+            }
+            ReadResult::StreamDeleted(stream_name) => {
+                return Err(RepositoryError::StreamDeleted(stream_name))
+            }
+            ReadResult::Ok(mut stream) => {
+                while let Some(event) = stream.try_next().await? {
+                    let event = event.get_original_event();
+
+                    state.apply(event.decode_with(&self.codec)?);
+                    revision = Some(event.revision);
+                }
+            }
+        }
+
+        let mut root = AggregateRoot::new(id, revision, state);
+        root.snapshot_revision = snapshot_revision;
+
+        Ok(root)
+    }
+
+    async fn load_snapshot(&self, id: &str) -> Result<Option<(A, u64)>, RepositoryError<A::Error>> {
+        let snapshots = match self.snapshots.as_ref() {
+            Some(snapshots) => snapshots,
+            None => return Ok(None),
+        };
+
+        let options = self
+            .read_options
+            .clone()
+            .position(StreamPosition::End)
+            .backwards();
+
+        match self
+            .client
+            .read_stream(A::snapshot_stream_name(id), &options, Single)
+            .await?
+        {
+            ReadResult::Ok(Some(event)) => {
+                Ok(Some((snapshots.decode)(event.get_original_event())?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn save_snapshot(&self, root: &mut AggregateRoot<A>) -> crate::Result<()> {
+        let (snapshots, revision) = match (self.snapshots.as_ref(), root.revision) {
+            (Some(snapshots), Some(revision)) => (snapshots, revision),
+            _ => return Ok(()),
+        };
+
+        let covered = root.snapshot_revision.map_or(0, |revision| revision + 1);
+
+        if (revision + 1).saturating_sub(covered) < snapshots.options.every {
+            return Ok(());
+        }
+
+        let snapshot_stream_name = A::snapshot_stream_name(root.id());
+        let options = self
+            .append_options
+            .clone()
+            .expected_revision(ExpectedRevision::Any);
+
+        if root.snapshot_revision.is_none() {
+            let mut metadata_options = UpdateStreamMetadataOptions::default();
+
+            if let Some(credentials) = self.append_options.credentials.clone() {
+                metadata_options = metadata_options.authenticated(credentials);
+            }
+
+            let max_count = snapshots.options.max_count;
+
+            self.client
+                .update_stream_metadata(
+                    snapshot_stream_name.as_str(),
+                    &metadata_options,
+                    |builder| builder.max_count(max_count),
+                )
+                .await?
+                .map_err(|e| crate::Error::WrongExpectedVersion {
+                    stream_name: format!("$${}", snapshot_stream_name),
+                    expected: Some(e.expected),
+                    current: Some(e.current),
+                    code: None,
+                })?;
+        }
+
+        let event = (snapshots.encode)(&root.state, revision)
+            .map_err(|e| crate::Error::InternalParsingError(e.to_string()))?;
+
+        self.client
+            .append_to_stream(snapshot_stream_name.as_str(), &options, event)
+            .await?
+            .map_err(|e| crate::Error::WrongExpectedVersion {
+                stream_name: snapshot_stream_name.clone(),
+                expected: Some(e.expected),
+                current: Some(e.current),
+                code: None,
+            })?;
+
+        root.snapshot_revision = Some(revision);
+
+        Ok(())
+    }
+
+    /// Appends the pending events of an aggregate. The write fails with
+    /// [`RepositoryError::Conflict`] if the stream moved since the aggregate was loaded.
+    pub async fn save(
+        &self,
+        root: &mut AggregateRoot<A>,
+    ) -> Result<Option<WriteResult>, RepositoryError<A::Error>> {
+        if root.pending.is_empty() {
+            return Ok(None);
+        }
+
+        let stream_name = A::stream_name(root.id());
+        let expected = root.expected_revision();
+        let mut events = Vec::with_capacity(root.pending.len());
+
+        for event in root.pending.iter() {
+            events.push(EventData::encode_with(
+                A::event_type(event),
+                &self.codec,
+                event,
+            )?);
+        }
+
+        let options = self.append_options.clone().expected_revision(expected);
+
+        match self
+            .client
+            .append_to_stream(stream_name.as_str(), &options, events)
+            .await?
+        {
+            Ok(result) => {
+                root.revision = Some(result.next_expected_version);
+                root.pending.clear();
+
+                // Events are already committed at this point, a missed snapshot only makes the
+                // next load slower.
+                if let Err(e) = self.save_snapshot(root).await {
+                    warn!("Failed to snapshot aggregate {}: {}", stream_name, e);
+                }
+
+                Ok(Some(result))
+            }
+
+            Err(e) => Err(RepositoryError::Conflict {
+                stream_name,
+                expected: e.expected,
+                current: e.current,
+            }),
+        }
+    }
+
+    /// Loads an aggregate, runs a command against it and saves the resulting events.
+    pub async fn execute(
+        &self,
+        id: impl AsRef<str>,
+        command: A::Command,
+    ) -> Result<AggregateRoot<A>, RepositoryError<A::Error>> {
+        let mut root = self.load(id).await?;
+
+        root.execute(command).map_err(RepositoryError::Rejected)?;
+        self.save(&mut root).await?;
+
+        Ok(root)
+    }
+}
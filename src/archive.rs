@@ -0,0 +1,472 @@
+//! Offloads the oldest events of a stream to a local archive file, then truncates the stream.
+//!
+//! An archive is a newline-delimited JSON file, one [`ArchivedEvent`] per line, holding
+//! everything needed to append the events again: ids, types, metadata and payloads.
+//!
+//! ```no_run
+//! # use eventstore::{ArchiveOptions, Archiver, Client};
+//! # use std::time::Duration;
+//! # async fn doc(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let archiver = Archiver::new(client);
+//! let options = ArchiveOptions::older_than(Duration::from_secs(90 * 24 * 3_600));
+//!
+//! let result = archiver
+//!     .archive("orders-42", "orders-42.ndjson", &options)
+//!     .await?;
+//!
+//! // Later on, brings the archived events back into a new stream.
+//! archiver
+//!     .restore("orders-42.ndjson", "orders-42-restored", &Default::default())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use uuid::Uuid;
+
+use crate::{
+    All, AppendToStreamOptions, Client, Credentials, EventData, ReadResult, ReadStreamOptions,
+    RecordedEvent, UpdateStreamMetadataOptions, WriteResult, WrongExpectedVersion,
+};
+
+/// An event as stored in an archive file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedEvent {
+    pub id: Uuid,
+    pub stream_id: String,
+    pub revision: u64,
+    pub event_type: String,
+    /// System metadata, `content-type` and `created` included.
+    pub metadata: HashMap<String, String>,
+    #[serde(
+        serialize_with = "serialize_base64",
+        deserialize_with = "deserialize_base64"
+    )]
+    pub custom_metadata: Bytes,
+    #[serde(
+        serialize_with = "serialize_base64",
+        deserialize_with = "deserialize_base64"
+    )]
+    pub data: Bytes,
+}
+
+impl From<RecordedEvent> for ArchivedEvent {
+    fn from(event: RecordedEvent) -> Self {
+        ArchivedEvent {
+            id: event.id,
+            stream_id: event.stream_id,
+            revision: event.revision,
+            event_type: event.event_type,
+            metadata: event.metadata,
+            custom_metadata: event.custom_metadata,
+            data: event.data,
+        }
+    }
+}
+
+impl From<ArchivedEvent> for EventData {
+    fn from(event: ArchivedEvent) -> Self {
+        let content_type = event
+            .metadata
+            .get("content-type")
+            .cloned()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        // Only the metadata a client is allowed to propose is kept, the rest is set by the server.
+        let mut metadata = HashMap::new();
+        metadata.insert("type".to_owned(), event.event_type);
+        metadata.insert("content-type".to_owned(), content_type);
+
+        EventData {
+            payload: event.data,
+            id_opt: Some(event.id),
+            metadata,
+            custom_metadata: Some(event.custom_metadata).filter(|b| !b.is_empty()),
+        }
+    }
+}
+
+fn serialize_base64<S>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&base64::encode(bytes))
+}
+
+fn deserialize_base64<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded = String::deserialize(deserializer)?;
+
+    base64::decode(encoded)
+        .map(Bytes::from)
+        .map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Archive I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed archive: {0}")]
+    Format(#[from] serde_json::Error),
+    #[error("Archive verification failed: {0}")]
+    Verification(String),
+    #[error("Stream {0} is deleted")]
+    StreamDeleted(String),
+    #[error("Wrong expected version when truncating the stream: {0}")]
+    WrongExpectedVersion(WrongExpectedVersion),
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArchiveBound {
+    Revision(u64),
+    OlderThan(Duration),
+}
+
+/// Which events of a stream get archived, and whether the stream is truncated afterward.
+#[derive(Clone)]
+pub struct ArchiveOptions {
+    bound: ArchiveBound,
+    truncate: bool,
+    credentials: Option<Credentials>,
+}
+
+impl ArchiveOptions {
+    /// Archives the events up to the given revision, included.
+    pub fn up_to_revision(revision: u64) -> Self {
+        Self::new(ArchiveBound::Revision(revision))
+    }
+
+    /// Archives the events written more than the given duration ago. Events are archived in
+    /// order, stopping at the first one that is recent enough or has no creation date.
+    pub fn older_than(age: Duration) -> Self {
+        Self::new(ArchiveBound::OlderThan(age))
+    }
+
+    fn new(bound: ArchiveBound) -> Self {
+        Self {
+            bound,
+            truncate: true,
+            credentials: None,
+        }
+    }
+
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Only writes the archive, leaving the stream untouched. By default, the stream is
+    /// truncated before the first event that wasn't archived.
+    pub fn keep_stream(self) -> Self {
+        Self {
+            truncate: false,
+            ..self
+        }
+    }
+}
+
+/// Outcome of an archival.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveResult {
+    /// How many events were written to the archive.
+    pub archived: usize,
+    /// Revision of the last archived event, if any event was archived.
+    pub last_revision: Option<u64>,
+    /// `truncate_before` value set on the stream, if it was truncated.
+    pub truncate_before: Option<u64>,
+}
+
+/// Archives streams to local files and restores them.
+#[derive(Clone)]
+pub struct Archiver {
+    client: Client,
+}
+
+impl Archiver {
+    pub fn new(client: Client) -> Self {
+        Archiver { client }
+    }
+
+    /// Writes the events of a stream selected by the options to an archive file, replacing it
+    /// if it exists. The events are first written to a temporary file next to it, which is read
+    /// back and checked against the events that were archived before taking the archive place.
+    /// Only then is the stream truncated, through its `truncate_before` metadata, so the
+    /// archived events are no longer readable and can be scavenged. When no event is selected,
+    /// the archive file is left untouched.
+    pub async fn archive(
+        &self,
+        stream_name: impl AsRef<str>,
+        path: impl AsRef<Path>,
+        options: &ArchiveOptions,
+    ) -> Result<ArchiveResult, ArchiveError> {
+        let stream_name = stream_name.as_ref();
+        let path = path.as_ref();
+        let mut read_options = ReadStreamOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        let mut events = match self
+            .client
+            .read_stream(stream_name, &read_options, All)
+            .await?
+        {
+            ReadResult::Ok(events) => Some(events),
+            ReadResult::StreamNotFound(_) => None,
+            ReadResult::StreamDeleted(stream_name) => {
+                return Err(ArchiveError::StreamDeleted(stream_name))
+            }
+        };
+
+        let cutoff = match options.bound {
+            ArchiveBound::OlderThan(age) => SystemTime::now().checked_sub(age),
+            ArchiveBound::Revision(_) => None,
+        };
+
+        // Events are written to a temporary file, only moved over the archive once verified, so
+        // an existing archive is never replaced by a partial or empty one.
+        let temp_path = temp_path(path);
+        let written = async {
+            let mut writer = None;
+            let mut archived = Vec::new();
+
+            while let Some(event) = match events.as_mut() {
+                Some(events) => events.try_next().await?,
+                None => None,
+            } {
+                let event = match event.event {
+                    Some(event) => event,
+                    None => continue,
+                };
+
+                let selected = match options.bound {
+                    ArchiveBound::Revision(revision) => event.revision <= revision,
+                    ArchiveBound::OlderThan(_) => event
+                        .created()
+                        .zip(cutoff)
+                        .is_some_and(|(created, cutoff)| created < cutoff),
+                };
+
+                if !selected {
+                    break;
+                }
+
+                let writer = match writer.as_mut() {
+                    Some(writer) => writer,
+                    None => {
+                        writer.insert(BufWriter::new(tokio::fs::File::create(&temp_path).await?))
+                    }
+                };
+
+                let event = ArchivedEvent::from(event);
+                let mut line = serde_json::to_vec(&event)?;
+                line.push(b'\n');
+
+                writer.write_all(&line).await?;
+                archived.push(event);
+            }
+
+            if let Some(mut writer) = writer {
+                writer.flush().await?;
+                writer.into_inner().sync_all().await?;
+
+                verify(&temp_path, &archived).await?;
+            }
+
+            Ok::<_, ArchiveError>(archived)
+        }
+        .await;
+
+        let archived = match written {
+            Ok(archived) => archived,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(e);
+            }
+        };
+
+        if !archived.is_empty() {
+            tokio::fs::rename(&temp_path, path).await?;
+        }
+
+        let last_revision = archived.last().map(|event| event.revision);
+        let mut truncate_before = None;
+
+        if let Some(last_revision) = last_revision.filter(|_| options.truncate) {
+            let mut metadata_options = UpdateStreamMetadataOptions::default();
+
+            if let Some(credentials) = options.credentials.clone() {
+                metadata_options = metadata_options.authenticated(credentials);
+            }
+
+            self.client
+                .update_stream_metadata(stream_name, &metadata_options, |metadata| {
+                    metadata.truncate_before(last_revision + 1)
+                })
+                .await?
+                .map_err(ArchiveError::WrongExpectedVersion)?;
+
+            truncate_before = Some(last_revision + 1);
+        }
+
+        Ok(ArchiveResult {
+            archived: archived.len(),
+            last_revision,
+            truncate_before,
+        })
+    }
+
+    /// Appends the events of an archive file to a stream, keeping their ids, types and
+    /// payloads. The events get new revisions in the target stream.
+    pub async fn restore(
+        &self,
+        path: impl AsRef<Path>,
+        stream_name: impl AsRef<str>,
+        options: &AppendToStreamOptions,
+    ) -> Result<Result<WriteResult, WrongExpectedVersion>, ArchiveError> {
+        let events = read_archive(path.as_ref())
+            .await?
+            .into_iter()
+            .map(EventData::from)
+            .collect::<Vec<_>>();
+
+        Ok(self
+            .client
+            .append_to_stream(stream_name, options, events)
+            .await?)
+    }
+}
+
+// Path of the file an archive is written to before being verified.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".tmp");
+
+    path.with_file_name(file_name)
+}
+
+/// Reads every event of an archive file.
+pub async fn read_archive(path: impl AsRef<Path>) -> Result<Vec<ArchivedEvent>, ArchiveError> {
+    let file = tokio::fs::File::open(path.as_ref()).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut events = Vec::new();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        events.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(events)
+}
+
+async fn verify(path: &Path, expected: &[ArchivedEvent]) -> Result<(), ArchiveError> {
+    let actual = read_archive(path).await?;
+
+    if actual.len() != expected.len() {
+        return Err(ArchiveError::Verification(format!(
+            "expected {} events, found {}",
+            expected.len(),
+            actual.len()
+        )));
+    }
+
+    for (event, expected) in actual.iter().zip(expected) {
+        if event.id != expected.id || event.revision != expected.revision {
+            return Err(ArchiveError::Verification(format!(
+                "expected event {} at revision {}, found event {} at revision {}",
+                expected.id, expected.revision, event.id, event.revision
+            )));
+        }
+
+        if event != expected {
+            return Err(ArchiveError::Verification(format!(
+                "event {} at revision {} differs from the archived copy",
+                expected.id, expected.revision
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use super::{read_archive, verify, ArchivedEvent};
+    use crate::EventData;
+    use bytes::Bytes;
+    use tokio::io::AsyncWriteExt;
+
+    fn archived(revision: u64) -> ArchivedEvent {
+        ArchivedEvent {
+            id: uuid::Uuid::new_v4(),
+            stream_id: "orders-42".to_string(),
+            revision,
+            event_type: "order-placed".to_string(),
+            metadata: [
+                ("type".to_string(), "order-placed".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+                ("created".to_string(), "16000000000000000".to_string()),
+            ]
+            .into(),
+            custom_metadata: Bytes::new(),
+            data: Bytes::from_static(b"{\"total\":42}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_archive_file() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("{}.ndjson", uuid::Uuid::new_v4()));
+        let events = vec![archived(0), archived(1)];
+        let mut file = tokio::fs::File::create(&path).await?;
+
+        for event in events.iter() {
+            file.write_all(serde_json::to_string(event)?.as_bytes())
+                .await?;
+            file.write_all(b"\n").await?;
+        }
+
+        file.sync_all().await?;
+
+        assert_eq!(read_archive(&path).await?, events);
+
+        assert!(verify(&path, &events).await.is_ok());
+        assert!(verify(&path, &events[..1]).await.is_err());
+
+        let mut corrupted = events.clone();
+        corrupted[1].data = Bytes::from_static(b"{\"total\":43}");
+        assert!(verify(&path, &corrupted).await.is_err());
+
+        let mut corrupted = events.clone();
+        corrupted[0].event_type = "order-cancelled".to_string();
+        assert!(verify(&path, &corrupted).await.is_err());
+
+        let _ = tokio::fs::remove_file(&path).await;
+
+        let data = EventData::from(events[0].clone());
+        assert_eq!(data.id_opt, Some(events[0].id));
+        assert_eq!(data.metadata.len(), 2);
+        assert_eq!(data.custom_metadata, None);
+
+        Ok(())
+    }
+}
@@ -1,15 +1,21 @@
-use crate::{EventData, ExpectedRevision, Position};
+use crate::options::retry::RetryOptions;
+use crate::{EventData, ExpectedRevision, Position, WrongExpectedVersion};
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use futures::channel::oneshot;
+use futures::future::Either;
 use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type BatchResult = crate::Result<Result<BatchWriteResult, BatchAppendError>>;
 
 #[derive(Debug)]
 pub(crate) struct In {
     req: Req,
-    sender: oneshot::Sender<crate::Result<BatchWriteResult>>,
+    sender: oneshot::Sender<BatchResult>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Req {
     pub(crate) id: uuid::Uuid,
     pub(crate) stream_name: String,
@@ -20,14 +26,132 @@ pub(crate) struct Req {
 #[derive(Debug)]
 pub(crate) struct Out {
     pub(crate) correlation_id: uuid::Uuid,
-    pub(crate) result: crate::Result<BatchWriteResult>,
+    pub(crate) result: BatchResult,
 }
 
 #[derive(Debug)]
 pub(crate) enum BatchMsg {
     In(In),
     Out(Out),
-    Error(crate::Error),
+    /// The session identified by `session` has failed.
+    Error {
+        session: u64,
+        error: crate::Error,
+    },
+}
+
+/// Reason why the server rejected a batch-append request.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BatchAppendError {
+    #[error("{0}")]
+    WrongExpectedVersion(WrongExpectedVersion),
+    #[error("Stream {stream_name} is deleted")]
+    StreamDeleted { stream_name: String },
+    #[error("Access denied")]
+    AccessDenied,
+    #[error("Maximum append size of {max_append_size} bytes exceeded")]
+    MaximumAppendSizeExceeded { max_append_size: u32 },
+    #[error("Request timed out on the server")]
+    Timeout,
+    #[error("Invalid transaction")]
+    InvalidTransaction,
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Batch-append request failed with {code}: {message}")]
+    Other { code: tonic::Code, message: String },
+}
+
+/// Opens a new batch-append session, identified by the given id. Requests sent to the
+/// returned sender are forwarded to the server.
+pub(crate) type Connector = Box<dyn Fn(u64) -> UnboundedSender<Req> + Send + Sync>;
+
+/// Health of a batch-append session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchAppendState {
+    /// The session is up and running.
+    Connected,
+
+    /// The session broke and a new one is being opened. Pending requests are resent once it's
+    /// opened.
+    Reconnecting,
+
+    /// The client gave up reconnecting and failed the pending requests. A new session is opened
+    /// on the next request.
+    Degraded,
+
+    /// The session is closed for good.
+    Closed,
+}
+
+/// Indicates opening a new session has a chance to succeed where the broken one failed: the
+/// server ended the session, the transport failed, or the node went away or lost its leadership.
+/// Unlike `Error::is_retryable`, this is about the session, not a single call.
+fn is_reconnectable(error: &crate::Error) -> bool {
+    matches!(
+        error,
+        crate::Error::GrpcConnectionError(_)
+            | crate::Error::ServerError { .. }
+            | crate::Error::NotLeaderException(_)
+    )
+}
+
+/// Groups events into chunks staying under a byte budget. An event bigger than the budget gets a
+/// chunk of its own.
+pub(crate) struct Chunker {
+    chunk_size: usize,
+    chunk: Vec<EventData>,
+    size: usize,
+}
+
+impl Chunker {
+    pub(crate) fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            chunk: Vec::new(),
+            size: 0,
+        }
+    }
+
+    /// Adds an event. Returns the previous chunk if the event didn't fit in it.
+    pub(crate) fn push(&mut self, event: EventData) -> Option<Vec<EventData>> {
+        let event_size = event.estimated_size();
+        let mut full = None;
+
+        if !self.chunk.is_empty() && self.size + event_size > self.chunk_size {
+            full = Some(std::mem::take(&mut self.chunk));
+            self.size = 0;
+        }
+
+        self.size += event_size;
+        self.chunk.push(event);
+
+        full
+    }
+
+    /// Returns the chunk being filled, possibly empty.
+    pub(crate) fn take(&mut self) -> Vec<EventData> {
+        self.size = 0;
+        std::mem::take(&mut self.chunk)
+    }
+}
+
+/// Splits events into chunks staying under `chunk_size` bytes. Always returns at least one chunk.
+pub(crate) fn chunk_events(
+    events: Vec<EventData>,
+    chunk_size: Option<usize>,
+) -> Vec<Vec<EventData>> {
+    let mut chunker = match chunk_size {
+        Some(chunk_size) => Chunker::new(chunk_size),
+        None => return vec![events],
+    };
+
+    let mut chunks: Vec<_> = events
+        .into_iter()
+        .filter_map(|event| chunker.push(event))
+        .collect();
+
+    chunks.push(chunker.take());
+    chunks
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -72,36 +196,91 @@ impl BatchWriteResult {
 
 pub struct BatchAppendClient {
     sender: UnboundedSender<BatchMsg>,
+    state: Arc<Mutex<BatchAppendState>>,
 }
 
 impl BatchAppendClient {
     pub(crate) fn new(
         sender: UnboundedSender<BatchMsg>,
         mut receiver: UnboundedReceiver<BatchMsg>,
-        mut forward: UnboundedSender<Req>,
+        connector: Connector,
+        reconnect: RetryOptions,
     ) -> Self {
+        let state = Arc::new(Mutex::new(BatchAppendState::Connected));
+        let shared_state = state.clone();
+        let set_state = move |value| *shared_state.lock().unwrap() = value;
+
         tokio::spawn(async move {
-            let mut reg = std::collections::HashMap::<
-                uuid::Uuid,
-                oneshot::Sender<crate::Result<BatchWriteResult>>,
-            >::new();
-            while let Some(msg) = receiver.next().await {
+            let mut session = 0u64;
+            let mut forward = connector(session);
+            let mut attempts = 0usize;
+            let mut reg = HashMap::<uuid::Uuid, (Req, oneshot::Sender<BatchResult>)>::new();
+            // Set while waiting to open a new session. Requests and responses keep flowing
+            // meanwhile.
+            let mut reconnect_timer: Option<std::pin::Pin<Box<tokio::time::Sleep>>> = None;
+
+            loop {
+                let msg = match reconnect_timer.as_mut() {
+                    Some(timer) => match futures::future::select(receiver.next(), timer).await {
+                        Either::Left((msg, _)) => msg,
+                        Either::Right(_) => {
+                            reconnect_timer = None;
+                            attempts += 1;
+                            session += 1;
+                            forward = connector(session);
+                            debug!("Opening batch-append session {}", session);
+
+                            // Every event has an id at that point, which makes resent appends
+                            // idempotent on the server.
+                            for (req, _) in reg.values() {
+                                let _ = forward.send(req.clone()).await;
+                            }
+
+                            continue;
+                        }
+                    },
+                    None => receiver.next().await,
+                };
+
+                let msg = match msg {
+                    Some(msg) => msg,
+                    None => break,
+                };
+
                 match msg {
                     BatchMsg::In(msg) => {
                         let correlation_id = msg.req.id;
-                        if forward.send(msg.req).await.is_ok() {
-                            reg.insert(correlation_id, msg.sender);
-                            debug!("Send batch-append request {}", correlation_id);
 
-                            continue;
+                        // While reconnecting, the request is sent once the new session is opened.
+                        if reconnect_timer.is_none() {
+                            if forward.is_closed() {
+                                session += 1;
+                                forward = connector(session);
+                                set_state(BatchAppendState::Reconnecting);
+                                debug!("Opening batch-append session {}", session);
+
+                                for (req, _) in reg.values() {
+                                    let _ = forward.send(req.clone()).await;
+                                }
+                            }
+
+                            // If the session is gone, the request will be resent once a new
+                            // session is opened.
+                            let _ = forward.send(msg.req.clone()).await;
                         }
 
-                        error!("Batch-append session has been closed");
-                        break;
+                        reg.insert(correlation_id, (msg.req, msg.sender));
+                        debug!("Send batch-append request {}", correlation_id);
                     }
 
                     BatchMsg::Out(resp) => {
-                        if let Some(entry) = reg.remove(&resp.correlation_id) {
+                        // Responses of the broken session can still arrive while reconnecting.
+                        if reconnect_timer.is_none() {
+                            attempts = 0;
+                            set_state(BatchAppendState::Connected);
+                        }
+
+                        if let Some((_, entry)) = reg.remove(&resp.correlation_id) {
                             let _ = entry.send(resp.result);
 
                             continue;
@@ -113,18 +292,51 @@ impl BatchAppendClient {
                         );
                     }
 
-                    BatchMsg::Error(e) => {
-                        for (_, resp_sender) in reg {
-                            let _ = resp_sender.send(Err(e.clone()));
+                    BatchMsg::Error { session: id, error } => {
+                        if id != session || reconnect_timer.is_some() {
+                            debug!("Ignoring error from stale batch-append session {}", id);
+                            continue;
                         }
 
-                        break;
+                        if !is_reconnectable(&error) || !reconnect.can_retry(attempts) {
+                            error!("Batch-append session {} failed: {}", session, error);
+
+                            forward.close_channel();
+                            attempts = 0;
+                            set_state(BatchAppendState::Degraded);
+
+                            for (_, (_, resp_sender)) in reg.drain() {
+                                let _ = resp_sender.send(Err(error.clone()));
+                            }
+
+                            continue;
+                        }
+
+                        warn!(
+                            "Batch-append session {} broke: {}. Reconnecting ({}/{})",
+                            session,
+                            error,
+                            attempts + 1,
+                            reconnect.limit
+                        );
+
+                        forward.close_channel();
+                        set_state(BatchAppendState::Reconnecting);
+                        reconnect_timer =
+                            Some(Box::pin(tokio::time::sleep(reconnect.delay_for(attempts))));
                     }
                 }
             }
+
+            set_state(BatchAppendState::Closed);
         });
 
-        Self { sender }
+        Self { sender, state }
+    }
+
+    /// Returns the current health of the batch-append session.
+    pub fn state(&self) -> BatchAppendState {
+        *self.state.lock().unwrap()
     }
 
     pub async fn append_to_stream<S: AsRef<str>>(
@@ -132,8 +344,16 @@ impl BatchAppendClient {
         stream_name: S,
         expected_revision: ExpectedRevision,
         events: Vec<EventData>,
-    ) -> crate::Result<BatchWriteResult> {
+    ) -> crate::Result<Result<BatchWriteResult, BatchAppendError>> {
         let (sender, receiver) = oneshot::channel();
+        // Ids are assigned upfront so the request can be resent safely if the session breaks.
+        let events = events
+            .into_iter()
+            .map(|event| match event.id_opt {
+                Some(_) => event,
+                None => event.id(uuid::Uuid::new_v4()),
+            })
+            .collect();
         let req = Req {
             id: uuid::Uuid::new_v4(),
             stream_name: stream_name.as_ref().to_string(),
@@ -147,7 +367,10 @@ impl BatchAppendClient {
             error!("[sending-end] Batch-append stream is closed: {}", e);
 
             let status = tonic::Status::cancelled("Batch-append stream has been closed");
-            return Err(crate::Error::ServerError(status.to_string()));
+            return Err(crate::Error::ServerError {
+                code: Some(status.code()),
+                message: status.to_string(),
+            });
         }
 
         match receiver.await {
@@ -156,10 +379,222 @@ impl BatchAppendClient {
 
                 let status = tonic::Status::cancelled("Batch-append stream has been closed");
 
-                Err(crate::Error::ServerError(status.to_string()))
+                Err(crate::Error::ServerError {
+                    code: Some(status.code()),
+                    message: status.to_string(),
+                })
             }
 
             Ok(result) => result,
         }
     }
 }
+
+#[cfg(test)]
+mod batch_tests {
+    use super::{
+        chunk_events, BatchAppendClient, BatchAppendState, BatchMsg, BatchWriteResult, Out, Req,
+    };
+    use crate::options::retry::RetryOptions;
+    use crate::{EventData, ExpectedRevision};
+    use futures::channel::mpsc;
+    use futures::{SinkExt, StreamExt};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn split_events_under_byte_budget() {
+        let events = (0..5)
+            .map(|_| EventData::binary("foo", vec![0u8; 100].into()))
+            .collect::<Vec<_>>();
+        let event_size = events[0].estimated_size();
+
+        let chunks = chunk_events(events.clone(), None);
+        assert_eq!(chunks.len(), 1);
+
+        let chunks = chunk_events(events.clone(), Some(event_size * 2));
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+
+        let chunks = chunk_events(events, Some(1));
+        assert_eq!(chunks.len(), 5);
+
+        let chunks = chunk_events(Vec::new(), Some(1));
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_empty());
+    }
+
+    #[tokio::test]
+    async fn resend_pending_requests_on_new_session() -> crate::Result<()> {
+        let (sender, receiver) = mpsc::unbounded();
+        let received = Arc::new(Mutex::new(Vec::<(u64, Req)>::new()));
+        let session_sender = sender.clone();
+        let session_received = received.clone();
+
+        let connector = Box::new(move |session: u64| {
+            let (forward, mut requests) = mpsc::unbounded::<Req>();
+            let mut batch_sender = session_sender.clone();
+            let received = session_received.clone();
+
+            tokio::spawn(async move {
+                while let Some(req) = requests.next().await {
+                    received.lock().unwrap().push((session, req.clone()));
+
+                    // The first session breaks before answering.
+                    let msg = if session == 0 {
+                        BatchMsg::Error {
+                            session,
+                            error: crate::Error::ServerError {
+                                code: None,
+                                message: "boom".to_string(),
+                            },
+                        }
+                    } else {
+                        BatchMsg::Out(Out {
+                            correlation_id: req.id,
+                            result: Ok(Ok(BatchWriteResult::new(
+                                req.stream_name.clone(),
+                                Some(0),
+                                None,
+                                Some(req.expected_revision),
+                            ))),
+                        })
+                    };
+
+                    let _ = batch_sender.send(msg).await;
+                }
+            });
+
+            forward
+        });
+
+        let reconnect = RetryOptions::default().retry_delay(std::time::Duration::from_millis(1));
+        let client = BatchAppendClient::new(sender, receiver, connector, reconnect);
+        let event = EventData::binary("foo", "bar".into());
+
+        let result = client
+            .append_to_stream("foo-stream", ExpectedRevision::NoStream, vec![event])
+            .await?
+            .unwrap();
+
+        assert_eq!(result.stream_name(), "foo-stream");
+        assert_eq!(client.state(), BatchAppendState::Connected);
+
+        let received = received.lock().unwrap();
+
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0, 0);
+        assert_eq!(received[1].0, 1);
+        assert_eq!(received[0].1.id, received[1].1.id);
+        assert!(received[0].1.events[0].id_opt.is_some());
+        assert_eq!(
+            received[0].1.events[0].id_opt,
+            received[1].1.events[0].id_opt
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fail_pending_requests_on_unrecoverable_error() {
+        let (sender, receiver) = mpsc::unbounded();
+        let session_sender = sender.clone();
+
+        let connector = Box::new(move |session: u64| {
+            let (forward, mut requests) = mpsc::unbounded::<Req>();
+            let mut batch_sender = session_sender.clone();
+
+            tokio::spawn(async move {
+                while requests.next().await.is_some() {
+                    let _ = batch_sender
+                        .send(BatchMsg::Error {
+                            session,
+                            error: crate::Error::Unimplemented,
+                        })
+                        .await;
+                }
+            });
+
+            forward
+        });
+
+        let client = BatchAppendClient::new(sender, receiver, connector, Default::default());
+        let event = EventData::binary("foo", "bar".into());
+
+        let result = client
+            .append_to_stream("foo-stream", ExpectedRevision::Any, vec![event])
+            .await;
+
+        assert!(matches!(result, Err(crate::Error::Unimplemented)));
+        assert_eq!(client.state(), BatchAppendState::Degraded);
+    }
+
+    #[tokio::test]
+    async fn resend_pending_requests_when_server_closes_session() -> crate::Result<()> {
+        let (sender, receiver) = mpsc::unbounded();
+        let sessions = Arc::new(Mutex::new(Vec::<u64>::new()));
+        let session_sender = sender.clone();
+        let session_log = sessions.clone();
+
+        let connector = Box::new(move |session: u64| {
+            let (forward, mut requests) = mpsc::unbounded::<Req>();
+            let mut batch_sender = session_sender.clone();
+            let sessions = session_log.clone();
+
+            tokio::spawn(async move {
+                while let Some(req) = requests.next().await {
+                    sessions.lock().unwrap().push(session);
+
+                    // The server ends the first session cleanly, leaving the request unanswered.
+                    let msg = if session == 0 {
+                        BatchMsg::Error {
+                            session,
+                            error: crate::Error::ServerError {
+                                code: None,
+                                message: "Batch-append session ended by the server".to_string(),
+                            },
+                        }
+                    } else {
+                        BatchMsg::Out(Out {
+                            correlation_id: req.id,
+                            result: Ok(Ok(BatchWriteResult::new(
+                                req.stream_name.clone(),
+                                Some(0),
+                                None,
+                                Some(req.expected_revision),
+                            ))),
+                        })
+                    };
+
+                    let _ = batch_sender.send(msg).await;
+                }
+            });
+
+            forward
+        });
+
+        let reconnect = RetryOptions::default().retry_delay(std::time::Duration::from_millis(50));
+        let client = BatchAppendClient::new(sender, receiver, connector, reconnect);
+        let append = |stream_name: &'static str| {
+            client.append_to_stream(
+                stream_name,
+                ExpectedRevision::Any,
+                vec![EventData::binary("foo", "bar".into())],
+            )
+        };
+
+        // The second request shows up while the client waits to reconnect.
+        let (first, second) = futures::join!(append("foo-stream"), async {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            append("bar-stream").await
+        });
+
+        assert_eq!(first?.unwrap().stream_name(), "foo-stream");
+        assert_eq!(second?.unwrap().stream_name(), "bar-stream");
+        assert_eq!(client.state(), BatchAppendState::Connected);
+        assert_eq!(*sessions.lock().unwrap(), vec![0, 1, 1]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,612 @@
+//! Persists the progress of catch-up subscriptions, so they resume where they left off after
+//! a restart.
+//!
+//! ```no_run
+//! # use eventstore::{CheckpointOptions, Client, FileCheckpointStore};
+//! # use futures::TryStreamExt;
+//! # async fn doc(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let store = FileCheckpointStore::new("checkpoints");
+//! let mut sub = client
+//!     .subscribe_to_all_with_checkpoints(
+//!         "read-model",
+//!         store,
+//!         &Default::default(),
+//!         &CheckpointOptions::default().every_events(50),
+//!     )
+//!     .await?;
+//!
+//! while let Some(event) = sub.try_next().await? {
+//!     // The position of an event is saved once the next one is requested.
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::{
+    AppendToStreamOptions, Client, Credentials, EventData, Position, ReadResult, ReadStreamOptions,
+    ResolvedEvent, Single, StreamMetadataBuilder, StreamMetadataResult, StreamPosition, SubEvent,
+    UpdateStreamMetadataOptions,
+};
+
+const CHECKPOINT_EVENT_TYPE: &str = "checkpoint";
+
+/// How far a subscription went: a revision for a stream, a position for `$all`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Checkpoint {
+    Revision(u64),
+    Position(Position),
+}
+
+/// Stores the checkpoints of subscriptions, identified by a subscription id.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Returns the last checkpoint stored for the subscription, if any.
+    async fn load(&self, subscription_id: &str) -> crate::Result<Option<Checkpoint>>;
+
+    /// Replaces the checkpoint of the subscription.
+    async fn store(&self, subscription_id: &str, checkpoint: Checkpoint) -> crate::Result<()>;
+}
+
+#[async_trait]
+impl<S: CheckpointStore + ?Sized> CheckpointStore for Arc<S> {
+    async fn load(&self, subscription_id: &str) -> crate::Result<Option<Checkpoint>> {
+        self.as_ref().load(subscription_id).await
+    }
+
+    async fn store(&self, subscription_id: &str, checkpoint: Checkpoint) -> crate::Result<()> {
+        self.as_ref().store(subscription_id, checkpoint).await
+    }
+}
+
+/// A [`CheckpointStore`] keeping its checkpoints in memory. Mostly useful for testing.
+#[derive(Default, Clone)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: Arc<Mutex<HashMap<String, Checkpoint>>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn load(&self, subscription_id: &str) -> crate::Result<Option<Checkpoint>> {
+        Ok(self
+            .checkpoints
+            .lock()
+            .unwrap()
+            .get(subscription_id)
+            .copied())
+    }
+
+    async fn store(&self, subscription_id: &str, checkpoint: Checkpoint) -> crate::Result<()> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .insert(subscription_id.to_string(), checkpoint);
+
+        Ok(())
+    }
+}
+
+/// A [`CheckpointStore`] writing each checkpoint as a JSON file, named after the subscription
+/// id, in a directory. Files are replaced atomically. Characters of the id other than ASCII
+/// letters, digits, `-` and `_` are percent-encoded, so any id maps to a file of the directory.
+#[derive(Clone)]
+pub struct FileCheckpointStore {
+    directory: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Stores checkpoints in the given directory, created on the first write if needed.
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        FileCheckpointStore {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, subscription_id: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}.checkpoint", encode_file_name(subscription_id)))
+    }
+}
+
+fn encode_file_name(subscription_id: &str) -> String {
+    let mut name = String::with_capacity(subscription_id.len());
+
+    for byte in subscription_id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(byte as char);
+        } else {
+            let _ = write!(name, "%{:02X}", byte);
+        }
+    }
+
+    name
+}
+
+fn checkpoint_error(subscription_id: &str, e: impl std::fmt::Display) -> crate::Error {
+    crate::Error::CheckpointError(format!(
+        "Checkpoint of subscription {}: {}",
+        subscription_id, e
+    ))
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, subscription_id: &str) -> crate::Result<Option<Checkpoint>> {
+        match tokio::fs::read(self.path(subscription_id)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| checkpoint_error(subscription_id, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(checkpoint_error(subscription_id, e)),
+        }
+    }
+
+    async fn store(&self, subscription_id: &str, checkpoint: Checkpoint) -> crate::Result<()> {
+        let path = self.path(subscription_id);
+        let tmp = path.with_extension("checkpoint.tmp");
+        let bytes =
+            serde_json::to_vec(&checkpoint).map_err(|e| checkpoint_error(subscription_id, e))?;
+
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .map_err(|e| checkpoint_error(subscription_id, e))?;
+        tokio::fs::write(&tmp, bytes)
+            .await
+            .map_err(|e| checkpoint_error(subscription_id, e))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| checkpoint_error(subscription_id, e))
+    }
+}
+
+/// A [`CheckpointStore`] appending checkpoints to a stream per subscription, named
+/// `checkpoint-{subscription_id}` by default. The stream gets a `$maxCount` of 1 so older
+/// checkpoints can be scavenged.
+#[derive(Clone)]
+pub struct StreamCheckpointStore {
+    client: Client,
+    prefix: String,
+    credentials: Option<Credentials>,
+    // Streams known to have their `$maxCount` set.
+    limited: Arc<Mutex<HashSet<String>>>,
+}
+
+impl StreamCheckpointStore {
+    pub fn new(client: Client) -> Self {
+        StreamCheckpointStore {
+            client,
+            prefix: "checkpoint-".to_string(),
+            credentials: None,
+            limited: Default::default(),
+        }
+    }
+
+    /// Prefix of the checkpoint streams, the subscription id being appended to it.
+    pub fn prefix(self, prefix: impl AsRef<str>) -> Self {
+        Self {
+            prefix: prefix.as_ref().to_string(),
+            ..self
+        }
+    }
+
+    /// Reads and writes the checkpoint streams with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    fn stream_name(&self, subscription_id: &str) -> String {
+        format!("{}{}", self.prefix, subscription_id)
+    }
+
+    fn read_options(&self) -> ReadStreamOptions {
+        let options = ReadStreamOptions::default();
+
+        match self.credentials.clone() {
+            Some(credentials) => options.authenticated(credentials),
+            None => options,
+        }
+    }
+
+    /// Sets a `$maxCount` of 1 on the checkpoint stream unless it already has one, leaving the
+    /// rest of its metadata untouched. Until that succeeds, it's checked again on every write.
+    async fn limit(&self, subscription_id: &str) -> crate::Result<()> {
+        let stream_name = self.stream_name(subscription_id);
+
+        if self.limited.lock().unwrap().contains(&stream_name) {
+            return Ok(());
+        }
+
+        let options = self
+            .read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let limited = match self
+            .client
+            .get_stream_metadata(stream_name.as_str(), &options)
+            .await?
+        {
+            StreamMetadataResult::Success(metadata) => metadata.metadata.max_count.is_some(),
+            _ => false,
+        };
+
+        if !limited {
+            let mut options = UpdateStreamMetadataOptions::default();
+
+            if let Some(credentials) = self.credentials.clone() {
+                options = options.authenticated(credentials);
+            }
+
+            let _ = self
+                .client
+                .update_stream_metadata(stream_name.as_str(), &options, |builder| {
+                    let metadata = builder.build();
+
+                    match metadata.max_count {
+                        Some(_) => StreamMetadataBuilder::from(metadata),
+                        None => StreamMetadataBuilder::from(metadata).max_count(1),
+                    }
+                })
+                .await?
+                .map_err(|e| checkpoint_error(subscription_id, e))?;
+        }
+
+        self.limited.lock().unwrap().insert(stream_name);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for StreamCheckpointStore {
+    async fn load(&self, subscription_id: &str) -> crate::Result<Option<Checkpoint>> {
+        let options = self
+            .read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let event = match self
+            .client
+            .read_stream(self.stream_name(subscription_id), &options, Single)
+            .await?
+        {
+            ReadResult::Ok(Some(event)) => event,
+            _ => return Ok(None),
+        };
+
+        event
+            .get_original_event()
+            .as_json()
+            .map(Some)
+            .map_err(|e| checkpoint_error(subscription_id, e))
+    }
+
+    async fn store(&self, subscription_id: &str, checkpoint: Checkpoint) -> crate::Result<()> {
+        let stream_name = self.stream_name(subscription_id);
+        let mut options = AppendToStreamOptions::default();
+
+        if let Some(credentials) = self.credentials.clone() {
+            options = options.authenticated(credentials);
+        }
+
+        let event = EventData::json(CHECKPOINT_EVENT_TYPE, checkpoint)
+            .map_err(|e| checkpoint_error(subscription_id, e))?;
+
+        let _ = self
+            .client
+            .append_to_stream(stream_name.as_str(), &options, event)
+            .await?
+            .map_err(|e| checkpoint_error(subscription_id, e))?;
+
+        // Only the latest checkpoint needs to be kept.
+        self.limit(subscription_id).await
+    }
+}
+
+/// When a subscription saves its checkpoint. Checkpoints sent by the server for filtered `$all`
+/// subscriptions are always saved, as they are already spaced out by the server.
+#[derive(Clone, Copy, Debug)]
+pub struct CheckpointOptions {
+    every_events: Option<usize>,
+    every: Option<Duration>,
+}
+
+impl Default for CheckpointOptions {
+    fn default() -> Self {
+        Self {
+            every_events: Some(100),
+            every: None,
+        }
+    }
+}
+
+impl CheckpointOptions {
+    /// Saves a checkpoint once this many events were processed since the last one. Default: 100.
+    pub fn every_events(self, count: usize) -> Self {
+        Self {
+            every_events: Some(count.max(1)),
+            ..self
+        }
+    }
+
+    /// Saves a checkpoint once this much time elapsed since the last one. A subscription waiting
+    /// for events still saves the checkpoint of the last one when the interval elapses. Can be
+    /// combined with `every_events`, whichever comes first.
+    pub fn every(self, interval: Duration) -> Self {
+        Self {
+            every: Some(interval),
+            ..self
+        }
+    }
+
+    /// Only saves checkpoints based on time.
+    pub fn time_only(self, interval: Duration) -> Self {
+        Self {
+            every_events: None,
+            every: Some(interval),
+        }
+    }
+}
+
+/// Saves the checkpoint of an event once the next item of the subscription is requested, that
+/// is, once the caller is done with it. The last checkpoint not saved yet is saved when the
+/// subscription ends or fails. Events are delivered at least once.
+pub(crate) fn track<'a, S, F>(
+    mut sub: BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>,
+    store: S,
+    subscription_id: String,
+    options: CheckpointOptions,
+    checkpoint_of: F,
+) -> BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>
+where
+    S: CheckpointStore + 'a,
+    F: Fn(&ResolvedEvent) -> Checkpoint + Send + 'a,
+{
+    let stream = async_stream::try_stream! {
+        let mut since_checkpoint = 0usize;
+        let mut last_saved = Instant::now();
+        let mut pending = None;
+
+        loop {
+            let next = match (pending, options.every) {
+                // Saves the pending checkpoint if no event shows up in time.
+                (Some(checkpoint), Some(every)) => {
+                    match tokio::time::timeout_at(last_saved + every, sub.try_next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            store.store(subscription_id.as_str(), checkpoint).await?;
+                            since_checkpoint = 0;
+                            last_saved = Instant::now();
+                            pending = None;
+                            continue;
+                        }
+                    }
+                }
+                _ => sub.try_next().await,
+            };
+
+            // Events handed out so far were processed, their progress is saved before leaving.
+            let sub_event = match next {
+                Ok(Some(sub_event)) => sub_event,
+                Ok(None) => {
+                    if let Some(checkpoint) = pending {
+                        store.store(subscription_id.as_str(), checkpoint).await?;
+                    }
+
+                    break;
+                }
+                Err(e) => {
+                    if let Some(checkpoint) = pending {
+                        if let Err(store_error) = store.store(subscription_id.as_str(), checkpoint).await {
+                            error!("Couldn't save the checkpoint of subscription {}: {}", subscription_id, store_error);
+                        }
+                    }
+
+                    Err(e)?;
+                    break;
+                }
+            };
+
+            let (checkpoint, forced) = match &sub_event {
+                SubEvent::EventAppeared(event) => (Some(checkpoint_of(event)), false),
+                SubEvent::Checkpoint(position) => (Some(Checkpoint::Position(*position)), true),
+                SubEvent::Confirmed(_) | SubEvent::CaughtUp | SubEvent::FellBehind => {
+                    (None, false)
+                }
+            };
+
+            yield sub_event;
+
+            if let Some(checkpoint) = checkpoint {
+                since_checkpoint += 1;
+
+                let due = forced
+                    || options.every_events.is_some_and(|n| since_checkpoint >= n)
+                    || options.every.is_some_and(|d| last_saved.elapsed() >= d);
+
+                if due {
+                    store.store(subscription_id.as_str(), checkpoint).await?;
+                    since_checkpoint = 0;
+                    last_saved = Instant::now();
+                    pending = None;
+                } else {
+                    pending = Some(checkpoint);
+                }
+            }
+        }
+    };
+
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use super::{
+        encode_file_name, track, Checkpoint, CheckpointOptions, CheckpointStore,
+        FileCheckpointStore, InMemoryCheckpointStore,
+    };
+    use crate::{Position, RecordedEvent, ResolvedEvent, SubEvent};
+    use futures::{StreamExt, TryStreamExt};
+    use std::time::Duration;
+
+    fn event(revision: u64) -> ResolvedEvent {
+        ResolvedEvent {
+            event: Some(RecordedEvent {
+                stream_id: "foo".to_string(),
+                id: uuid::Uuid::new_v4(),
+                revision,
+                event_type: "foo-event".to_string(),
+                data: Default::default(),
+                metadata: Default::default(),
+                custom_metadata: Default::default(),
+                is_json: true,
+                position: Position::start(),
+            }),
+            link: None,
+            commit_position: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn save_checkpoints_by_count_and_server_checkpoint() -> crate::Result<()> {
+        let store = InMemoryCheckpointStore::new();
+        let server_checkpoint = Position {
+            commit: 42,
+            prepare: 42,
+        };
+        let items = vec![
+            Ok(SubEvent::Confirmed("sub".to_string())),
+            Ok(SubEvent::EventAppeared(event(0))),
+            Ok(SubEvent::EventAppeared(event(1))),
+            Ok(SubEvent::EventAppeared(event(2))),
+        ];
+
+        let mut sub = track(
+            Box::pin(futures::stream::iter(items)),
+            store.clone(),
+            "sub".to_string(),
+            CheckpointOptions::default().every_events(2),
+            |e| Checkpoint::Revision(e.get_original_event().revision),
+        );
+
+        let _ = sub.try_next().await?;
+        let _ = sub.try_next().await?;
+        let _ = sub.try_next().await?;
+
+        // The second event isn't saved before the caller asks for the next item.
+        assert_eq!(store.load("sub").await?, None);
+
+        let _ = sub.try_next().await?;
+
+        assert_eq!(store.load("sub").await?, Some(Checkpoint::Revision(1)));
+
+        let items = vec![Ok(SubEvent::Checkpoint(server_checkpoint))];
+        let sub = track(
+            Box::pin(futures::stream::iter(items)),
+            store.clone(),
+            "sub".to_string(),
+            CheckpointOptions::default(),
+            |e| Checkpoint::Position(e.get_original_event().position),
+        );
+
+        let _ = sub.try_collect::<Vec<_>>().await?;
+
+        assert_eq!(
+            store.load("sub").await?,
+            Some(Checkpoint::Position(server_checkpoint))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn file_store_round_trip() -> crate::Result<()> {
+        let directory = std::env::temp_dir().join(format!("checkpoints-{}", uuid::Uuid::new_v4()));
+        let store = FileCheckpointStore::new(&directory);
+
+        assert_eq!(store.load("sub").await?, None);
+
+        store.store("sub", Checkpoint::Revision(41)).await?;
+        store.store("sub", Checkpoint::Revision(42)).await?;
+
+        assert_eq!(store.load("sub").await?, Some(Checkpoint::Revision(42)));
+
+        let _ = std::fs::remove_dir_all(&directory);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn save_pending_checkpoint_when_idle() -> crate::Result<()> {
+        let store = InMemoryCheckpointStore::new();
+        let items = futures::stream::iter(vec![Ok(SubEvent::EventAppeared(event(0)))])
+            .chain(futures::stream::pending());
+
+        let mut sub = track(
+            Box::pin(items),
+            store.clone(),
+            "sub".to_string(),
+            CheckpointOptions::default().time_only(Duration::from_millis(10)),
+            |e| Checkpoint::Revision(e.get_original_event().revision),
+        );
+
+        let _ = sub.try_next().await?;
+
+        // No more events come, the checkpoint gets saved while waiting for the next one.
+        let _ = tokio::time::timeout(Duration::from_millis(100), sub.try_next()).await;
+
+        assert_eq!(store.load("sub").await?, Some(Checkpoint::Revision(0)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn save_pending_checkpoint_when_subscription_ends() -> crate::Result<()> {
+        let store = InMemoryCheckpointStore::new();
+        let items = vec![
+            Ok(SubEvent::EventAppeared(event(0))),
+            Ok(SubEvent::EventAppeared(event(1))),
+            Err(crate::Error::ConnectionClosed),
+        ];
+
+        let sub = track(
+            Box::pin(futures::stream::iter(items)),
+            store.clone(),
+            "sub".to_string(),
+            CheckpointOptions::default(),
+            |e| Checkpoint::Revision(e.get_original_event().revision),
+        );
+
+        assert!(sub.try_collect::<Vec<_>>().await.is_err());
+        assert_eq!(store.load("sub").await?, Some(Checkpoint::Revision(1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_subscription_ids_as_file_names() {
+        assert_eq!(encode_file_name("read-model_1"), "read-model_1");
+        assert_eq!(encode_file_name("../etc/passwd"), "%2E%2E%2Fetc%2Fpasswd");
+        assert_eq!(encode_file_name("a\\b c"), "a%5Cb%20c");
+    }
+}
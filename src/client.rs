@@ -1,27 +1,52 @@
 use crate::batch::BatchAppendClient;
+use crate::checkpoint::{Checkpoint, CheckpointOptions, CheckpointStore};
+use crate::commands::LiveHead;
 use crate::options::batch_append::BatchAppendOptions;
+use crate::options::list_streams::ListStreamsOptions;
 use crate::options::persistent_subscription::PersistentSubscriptionOptions;
 use crate::options::read_all::ReadAllOptions;
+use crate::options::read_decide_append::ReadDecideAppendOptions;
 use crate::options::read_stream::ReadStreamOptions;
+use crate::options::retry::RetryOptions;
+use crate::options::stream_state::StreamStateOptions;
 use crate::options::subscribe_to_stream::SubscribeToStreamOptions;
+use crate::options::update_stream_metadata::UpdateStreamMetadataOptions;
+use crate::types::SOFT_DELETED_TRUNCATE_BEFORE;
 use crate::{
     commands, DeletePersistentSubscriptionOptions, DeleteStreamOptions,
     GetPersistentSubscriptionInfoOptions, ListPersistentSubscriptionsOptions,
     PersistentSubscriptionInfo, PersistentSubscriptionToAllOptions, Position, ReadResult,
-    ReplayParkedMessagesOptions, ResolvedEvent, StreamMetadata, StreamMetadataResult, SubEvent,
-    SubscribeToAllOptions, SubscribeToPersistentSubscriptionn, SubscriptionRead, SubscriptionWrite,
-    ToCount, TombstoneStreamOptions, VersionedMetadata, WriteResult, WrongExpectedVersion,
+    ReplayParkedMessagesOptions, ResolvedEvent, RetriedWriteResult, StreamMetadata,
+    StreamMetadataBuilder, StreamMetadataResult, StreamName, StreamPosition, StreamState, SubEvent,
+    SubscribeToAllOptions, SubscribeToPersistentSubscriptionn, SubscriptionDropReason,
+    SubscriptionRead, SubscriptionWrite, SystemSettings, ToCount, TombstoneStreamOptions,
+    VersionedMetadata, WriteResult, WrongExpectedVersion,
 };
 use crate::{
     grpc::{ClientSettings, GrpcClient},
-    Single,
+    All, ExpectedRevision, Single,
 };
 use crate::{
     options::append_to_stream::{AppendToStreamOptions, ToEvents},
     EventData,
 };
 use futures::stream::BoxStream;
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::HashSet;
+use std::future::Future;
+
+// How many `$streams` links are read at once when listing streams.
+const LIST_STREAMS_PAGE_SIZE: usize = 500;
+// How long a filtered `$all` subscription looks for the last matching event before relying on
+// server notifications only.
+const FILTERED_HEAD_LOOKUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Extracts the target stream name of a broken link.
+fn linked_stream_name(event: &ResolvedEvent) -> Option<String> {
+    let (stream_name, _) = event.link.as_ref()?.link_target()?;
+
+    Some(stream_name.to_string())
+}
 
 /// Represents a client to a single node. `Client` maintains a full duplex
 /// communication to EventStoreDB.
@@ -53,6 +78,15 @@ impl Client {
         })
     }
 
+    /// Retries idempotent operations failing with a retryable error, like a leader change or a
+    /// connection reset: reads, stream metadata reads and appends where every event has an
+    /// explicit id. Operations can override this policy with their own `retry_options`.
+    /// Default: no retry.
+    pub fn with_retry_options(mut self, options: RetryOptions) -> Self {
+        self.client.set_default_retry(Some(options));
+        self
+    }
+
     /// Sends events to a given stream.
     pub async fn append_to_stream<Events>(
         &self,
@@ -63,7 +97,29 @@ impl Client {
     where
         Events: ToEvents + 'static,
     {
-        commands::append_to_stream(&self.client, stream_name, options, events.into_events()).await
+        match options.chunk_size {
+            None => {
+                commands::append_to_stream(
+                    &self.client,
+                    stream_name,
+                    options,
+                    events.into_events(),
+                    Events::BUFFERED,
+                )
+                .await
+            }
+
+            Some(chunk_size) => {
+                commands::append_to_stream_in_chunks(
+                    &self.client,
+                    stream_name,
+                    options,
+                    chunk_size,
+                    events.into_events(),
+                )
+                .await
+            }
+        }
     }
 
     // Sets a stream metadata.
@@ -80,6 +136,240 @@ impl Client {
             .await
     }
 
+    /// Reads the server-wide default ACLs from the `$settings` stream. Returns `None` if they
+    /// were never set. Usually requires admin credentials.
+    pub async fn get_system_settings(
+        &self,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<Option<SystemSettings>> {
+        let options = options.clone().position(StreamPosition::End);
+
+        match self.read_stream("$settings", &options, Single).await? {
+            ReadResult::Ok(Some(event)) => {
+                let settings = event
+                    .get_original_event()
+                    .as_json::<SystemSettings>()
+                    .map_err(|e| crate::Error::InternalParsingError(e.to_string()))?;
+
+                Ok(Some(settings))
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    /// Writes the server-wide default ACLs to the `$settings` stream. Fails with
+    /// `Error::InvalidRole` if a role name is invalid. Usually requires admin credentials.
+    pub async fn set_system_settings(
+        &self,
+        options: &AppendToStreamOptions,
+        settings: SystemSettings,
+    ) -> crate::Result<Result<WriteResult, WrongExpectedVersion>> {
+        settings.validate()?;
+
+        let event = EventData::json("$settings", settings)
+            .map_err(|e| crate::Error::InternalParsingError(e.to_string()))?;
+
+        self.append_to_stream("$settings", options, event).await
+    }
+
+    /// Loads a stream metadata, lets `update` edit it through a builder seeded with the current
+    /// values, custom properties included, then writes it back expecting the revision that was
+    /// read. If the metadata was updated in the meantime, the whole cycle is retried according to
+    /// the retry options.
+    ///
+    /// ```no_run
+    /// # use eventstore::Client;
+    /// # async fn doc(client: Client) -> eventstore::Result<()> {
+    /// let result = client
+    ///     .update_stream_metadata("orders", &Default::default(), |metadata| {
+    ///         metadata
+    ///             .max_count(1_000)
+    ///             .insert_custom_property("owner", "billing")
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update_stream_metadata<F>(
+        &self,
+        stream_name: impl AsRef<str>,
+        options: &UpdateStreamMetadataOptions,
+        mut update: F,
+    ) -> crate::Result<Result<RetriedWriteResult, WrongExpectedVersion>>
+    where
+        F: FnMut(StreamMetadataBuilder) -> StreamMetadataBuilder,
+    {
+        let stream_name = stream_name.as_ref();
+        let metadata_stream_name = format!("$${}", stream_name);
+        let mut read_options = ReadStreamOptions::default().position(StreamPosition::End);
+        let mut append_options = AppendToStreamOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials.clone());
+            append_options = append_options.authenticated(credentials);
+        }
+
+        let read_options = &read_options;
+        let read = move || async move {
+            match self.get_stream_metadata(stream_name, read_options).await? {
+                StreamMetadataResult::Success(metadata) => Ok((
+                    StreamMetadataBuilder::from(metadata.metadata),
+                    ExpectedRevision::Exact(metadata.version),
+                )),
+
+                StreamMetadataResult::NotFound(_) => {
+                    Ok((StreamMetadataBuilder::new(), ExpectedRevision::NoStream))
+                }
+
+                StreamMetadataResult::Deleted(stream_name) => Err(crate::Error::StreamDeleted {
+                    stream_name,
+                    code: None,
+                }),
+            }
+        };
+
+        let decide = |builder| {
+            let event = EventData::json("$metadata", update(builder).build())
+                .map_err(|e| crate::Error::InternalParsingError(e.to_string()));
+
+            async move { Ok(vec![event?]) }
+        };
+
+        self.read_decide_append_with(
+            metadata_stream_name.as_str(),
+            append_options,
+            &options.retry,
+            read,
+            decide,
+        )
+        .await
+    }
+
+    /// Reads a stream, asks `decide` which events to append given what was read, then appends
+    /// them with the revision that was read as expected revision. If the stream was written to
+    /// in the meantime, the whole cycle is retried according to the retry options.
+    ///
+    /// ```no_run
+    /// # use eventstore::{Client, EventData};
+    /// # async fn doc(client: Client) -> eventstore::Result<()> {
+    /// let result = client
+    ///     .read_decide_append("counter", &Default::default(), |events| async move {
+    ///         let payload = serde_json::json!({ "count": events.len() + 1 });
+    ///
+    ///         Ok::<_, eventstore::Error>(vec![EventData::json("incremented", payload).unwrap()])
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_decide_append<F, Fut, E>(
+        &self,
+        stream_name: impl AsRef<str>,
+        options: &ReadDecideAppendOptions,
+        decide: F,
+    ) -> Result<Result<RetriedWriteResult, WrongExpectedVersion>, E>
+    where
+        F: FnMut(Vec<ResolvedEvent>) -> Fut,
+        Fut: Future<Output = Result<Vec<EventData>, E>>,
+        E: From<crate::Error>,
+    {
+        let stream_name = stream_name.as_ref();
+        let mut read_options = ReadStreamOptions::default();
+        let mut append_options = AppendToStreamOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials.clone());
+            append_options = append_options.authenticated(credentials);
+        }
+
+        let read_options = &read_options;
+        let read = move || async move {
+            match self.read_stream(stream_name, read_options, All).await? {
+                ReadResult::Ok(stream) => {
+                    let events = stream.try_collect::<Vec<_>>().await?;
+                    let expected = events.last().map_or(ExpectedRevision::NoStream, |event| {
+                        ExpectedRevision::Exact(event.get_original_event().revision)
+                    });
+
+                    Ok((events, expected))
+                }
+
+                ReadResult::StreamNotFound(_) => Ok((Vec::new(), ExpectedRevision::NoStream)),
+
+                ReadResult::StreamDeleted(stream_name) => Err(crate::Error::StreamDeleted {
+                    stream_name,
+                    code: None,
+                }
+                .into()),
+            }
+        };
+
+        self.read_decide_append_with(stream_name, append_options, &options.retry, read, decide)
+            .await
+    }
+
+    // Reads the state of a stream, decides which events to append from it, then appends them
+    // expecting the revision that was read. The whole cycle is retried as long as the append
+    // conflicts with a concurrent write, within the retry options.
+    async fn read_decide_append_with<S, R, RFut, D, DFut, E>(
+        &self,
+        stream_name: &str,
+        append_options: AppendToStreamOptions,
+        retry: &RetryOptions,
+        mut read: R,
+        mut decide: D,
+    ) -> Result<Result<RetriedWriteResult, WrongExpectedVersion>, E>
+    where
+        R: FnMut() -> RFut,
+        RFut: Future<Output = Result<(S, ExpectedRevision), E>>,
+        D: FnMut(S) -> DFut,
+        DFut: Future<Output = Result<Vec<EventData>, E>>,
+        E: From<crate::Error>,
+    {
+        let mut retries = 0usize;
+
+        loop {
+            let (state, expected) = read().await?;
+            let events = decide(state).await?;
+
+            if events.is_empty() {
+                return Ok(Ok(RetriedWriteResult {
+                    write_result: None,
+                    retries,
+                }));
+            }
+
+            let append_options = append_options.clone().expected_revision(expected);
+
+            match self
+                .append_to_stream(stream_name, &append_options, events)
+                .await?
+            {
+                Ok(write_result) => {
+                    return Ok(Ok(RetriedWriteResult {
+                        write_result: Some(write_result),
+                        retries,
+                    }))
+                }
+
+                Err(e) => {
+                    if !retry.can_retry(retries) {
+                        return Ok(Err(e));
+                    }
+
+                    debug!(
+                        "Write to stream {} conflicted ({}), retrying",
+                        stream_name, e
+                    );
+
+                    tokio::time::sleep(retry.delay_for(retries)).await;
+                    retries += 1;
+                }
+            }
+        }
+    }
+
     // Creates a batch-append client.
     pub async fn batch_append(
         &self,
@@ -130,6 +420,118 @@ impl Client {
         count.select(stream).await
     }
 
+    /// Lists the names of the existing streams, as indexed by the `$streams` system projection.
+    /// Fails with `Error::SystemProjectionUnavailable` if that projection is disabled, unless
+    /// the options opt in to scanning `$all` instead.
+    pub async fn list_streams(
+        &self,
+        options: &ListStreamsOptions,
+    ) -> crate::Result<BoxStream<'static, crate::Result<String>>> {
+        let mut read_options = ReadStreamOptions::default().resolve_link_tos();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        let first_page = self
+            .read_stream(
+                "$streams",
+                &read_options.clone().position(StreamPosition::Start),
+                LIST_STREAMS_PAGE_SIZE,
+            )
+            .await?;
+
+        let names: BoxStream<'static, crate::Result<String>> = match first_page {
+            ReadResult::Ok(page) => {
+                let client = self.clone();
+
+                // `$streams` is read page by page so that listing stops reading as soon as
+                // enough names were collected.
+                Box::pin(async_stream::stream! {
+                    let mut page = page;
+
+                    loop {
+                        let mut count = 0usize;
+                        let mut next = 0u64;
+
+                        while let Some(event) = page.next().await {
+                            let event = match event {
+                                Ok(event) => event,
+                                Err(e) => {
+                                    yield Err(e);
+                                    return;
+                                }
+                            };
+
+                            count += 1;
+                            next = event.get_original_event().revision + 1;
+
+                            // The projection links to the first event of every stream. That
+                            // event might be gone though, in which case the name is taken from
+                            // the link itself.
+                            let stream_name = match event.event.as_ref() {
+                                Some(target) => Some(target.stream_id.clone()),
+                                None => linked_stream_name(&event),
+                            };
+
+                            if let Some(stream_name) = stream_name {
+                                yield Ok(stream_name);
+                            }
+                        }
+
+                        if count < LIST_STREAMS_PAGE_SIZE {
+                            break;
+                        }
+
+                        let read_options = read_options.clone().position(StreamPosition::Position(next));
+
+                        page = match client.read_stream("$streams", &read_options, LIST_STREAMS_PAGE_SIZE).await {
+                            Ok(ReadResult::Ok(page)) => page,
+                            Ok(_) => break,
+                            Err(e) => {
+                                yield Err(e);
+                                break;
+                            }
+                        };
+                    }
+                })
+            }
+
+            _ if options.scan_all_fallback => {
+                warn!("$streams projection unavailable, listing streams by scanning $all");
+
+                let mut read_options = ReadAllOptions::default();
+
+                if let Some(credentials) = options.credentials.clone() {
+                    read_options = read_options.authenticated(credentials);
+                }
+
+                let mut seen = HashSet::new();
+                let events = self.read_all(&read_options, All).await?;
+
+                Box::pin(
+                    events
+                        .map_ok(|event| event.get_original_stream_id().to_string())
+                        .try_filter(move |name| futures::future::ready(seen.insert(name.clone()))),
+                )
+            }
+
+            _ => {
+                return Err(crate::Error::SystemProjectionUnavailable(
+                    "$streams".to_string(),
+                ))
+            }
+        };
+
+        let accepted = options.clone();
+        let names = names
+            .try_filter(move |name| futures::future::ready(accepted.accepts(name)))
+            .skip(options.skip)
+            .take(options.max_count.unwrap_or(usize::MAX));
+
+        Ok(Box::pin(names))
+    }
+
     /// Reads a stream metadata.
     pub async fn get_stream_metadata(
         &self,
@@ -165,6 +567,50 @@ impl Client {
         }
     }
 
+    /// Tells whether a stream exists, was deleted or soft-deleted, and if it exists, where it
+    /// currently ends. The stream last event and its metadata are read concurrently.
+    pub async fn stream_state(
+        &self,
+        stream_name: impl AsRef<str>,
+        options: &StreamStateOptions,
+    ) -> crate::Result<StreamState> {
+        let stream_name = stream_name.as_ref();
+        let read_options = options.read_options();
+
+        let (last, metadata) = futures::try_join!(
+            self.read_stream(stream_name, &read_options, Single),
+            self.get_stream_metadata(stream_name, &read_options),
+        )?;
+
+        let truncate_before = match metadata {
+            StreamMetadataResult::Success(metadata) => metadata.metadata.truncate_before,
+            _ => None,
+        };
+
+        let last = match last {
+            ReadResult::StreamDeleted(_) => return Ok(StreamState::Deleted),
+            ReadResult::StreamNotFound(_) => None,
+            ReadResult::Ok(last) => last,
+        };
+
+        let state = match (last, truncate_before) {
+            (Some(event), _) if truncate_before != Some(SOFT_DELETED_TRUNCATE_BEFORE) => {
+                let event = event.get_original_event();
+
+                StreamState::Exists {
+                    last_revision: event.revision,
+                    last_position: event.position,
+                    truncate_before,
+                }
+            }
+
+            (_, Some(_)) => StreamState::SoftDeleted,
+            (_, None) => StreamState::NotFound,
+        };
+
+        Ok(state)
+    }
+
     /// Soft deletes a given stream.
     /// Makes use of Truncate before. When a stream is deleted, its Truncate
     /// before is set to the streams current last event number. When a soft
@@ -205,60 +651,83 @@ impl Client {
     /// 100, and then any events subsequently written events until such time
     /// as the subscription is dropped or closed.
     ///
+    /// Once every event written before the subscription started was received,
+    /// `SubEvent::CaughtUp` is sent. With servers not sending that notification
+    /// themselves, the last revision of the stream is read alongside the
+    /// subscription and `CaughtUp` is sent once the event at that revision was
+    /// received. If that read fails, or the server sends notifications of its
+    /// own, only the server notifications are relied on.
+    ///
     /// [`subscribe_to_all`]: #method.subscribe_to_all_from
     pub async fn subscribe_to_stream<'a>(
         &self,
         stream_name: impl AsRef<str>,
         options: &SubscribeToStreamOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
+        let client = self.clone();
+        let head_stream_name = stream_name.as_ref().to_string();
+        let head_options = options.clone();
+        let sub = self
+            .subscribe_to_stream_with_retry(stream_name, options)
+            .await?;
+        let head = async move {
+            client
+                .stream_head(head_stream_name.as_str(), &head_options)
+                .await
+        };
+
+        Ok(commands::track_live_phase(sub, Box::pin(head)))
+    }
+
+    async fn subscribe_to_stream_with_retry<'a>(
+        &self,
+        stream_name: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
     ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
         match options.retry.as_ref().cloned() {
             None => commands::subscribe_to_stream(&self.client, stream_name, options).await,
             Some(retry) => {
                 let stream_name = stream_name.as_ref().to_string();
-                let mut attempt_count = 1usize;
+                let mut attempt = 0usize;
                 let mut offset = options.position;
                 let client = self.client.clone();
                 let mut options = options.clone();
                 let result = async_stream::stream! {
                     loop {
-                        match commands::subscribe_to_stream(&client, stream_name.as_str(), &options).await {
-                            Err(e) => {
-                                if attempt_count == retry.limit {
-                                    error!("Subscription: maximum retry threshold reached, cause: {}", e);
-
-                                    yield Err(e);
-                                    break;
-                                }
-
-                                error!("Subscription: attempt ({}/{}) failure, cause: {}", attempt_count, retry.limit, e);
-                                attempt_count += 1;
-                                tokio::time::sleep(retry.delay).await;
-                            }
-                            Ok(mut stream) => {
-                                loop {
-                                    match stream.try_next().await {
-                                        Ok(sub_event) => {
-                                            let sub_event = sub_event.expect("to be defined");
-                                            match sub_event {
-                                                crate::types::SubEvent::EventAppeared(event) => {
-                                                    offset = crate::types::StreamPosition::Position(event.get_original_event().revision);
-                                                    yield Ok(crate::types::SubEvent::EventAppeared(event));
-                                                }
-
-                                                ignored => yield Ok(ignored),
-                                            }
+                        let error = match commands::subscribe_to_stream(&client, stream_name.as_str(), &options).await {
+                            Err(e) => e,
+                            Ok(mut stream) => loop {
+                                match stream.try_next().await {
+                                    Ok(Some(sub_event)) => {
+                                        // Only progress resets the attempts, a subscription confirmed then dropped
+                                        // right away keeps backing off.
+                                        if let SubEvent::EventAppeared(event) = &sub_event {
+                                            attempt = 0;
+                                            offset = StreamPosition::Position(event.get_original_event().revision);
                                         }
-                                        Err(e) => {
-                                            attempt_count = 1;
-                                            options = options.start_from(offset);
 
-                                            error!("Subscription dropped cause: {}. Reconnecting", e);
-                                            break;
-                                        }
+                                        yield Ok(sub_event);
                                     }
+
+                                    Ok(None) => break crate::Error::ServerError { code: None, message: "Subscription ended by the server".to_string() },
+                                    Err(e) => break e,
                                 }
-                            }
+                            },
+                        };
+
+                        let reason = SubscriptionDropReason::from_error(&error);
+
+                        if !reason.is_recoverable() || !retry.can_retry(attempt) {
+                            error!("Subscription dropped ({:?}), giving up, cause: {}", reason, error);
+
+                            yield Err(crate::Error::SubscriptionDropped { reason, cause: Box::new(error) });
+                            break;
                         }
+
+                        error!("Subscription dropped ({:?}), attempt ({}/{}) to resubscribe, cause: {}", reason, attempt + 1, retry.limit, error);
+                        tokio::time::sleep(retry.delay_for(attempt)).await;
+                        attempt += 1;
+                        options = options.start_from(offset);
                     }
                 };
 
@@ -269,60 +738,79 @@ impl Client {
         }
     }
 
-    /// Like [`subscribe_to_stream`] but specific to system `$all` stream.
+    /// Like [`subscribe_to_stream`] but specific to system `$all` stream. Without server
+    /// notifications, `SubEvent::CaughtUp` is sent once an event or a checkpoint reaches the
+    /// position of the last event matching the subscription filter, read alongside the
+    /// subscription. With a filter, that lookup is given up after a couple of seconds, so it
+    /// doesn't scan most of `$all` when few events match.
     ///
     /// [`subscribe_to_stream`]: #method.subscribe_to_stream
     pub async fn subscribe_to_all<'a>(
         &self,
         options: &SubscribeToAllOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
+        let client = self.clone();
+        let head_options = options.clone();
+        let sub = self.subscribe_to_all_with_retry(options).await?;
+        let head = async move { client.all_head(&head_options).await };
+
+        Ok(commands::track_live_phase(sub, Box::pin(head)))
+    }
+
+    async fn subscribe_to_all_with_retry<'a>(
+        &self,
+        options: &SubscribeToAllOptions,
     ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
         match options.retry.as_ref().cloned() {
             None => commands::subscribe_to_all(&self.client, options).await,
             Some(retry) => {
-                let mut attempt_count = 1usize;
+                let mut attempt = 0usize;
                 let mut offset = options.position;
                 let client = self.client.clone();
                 let mut options = options.clone();
                 let result = async_stream::stream! {
                     loop {
-                        match commands::subscribe_to_all(&client, &options).await {
-                            Err(e) => {
-                                if attempt_count == retry.limit {
-                                    error!("Subscription: maximum retry threshold reached, cause: {}", e);
-
-                                    yield Err(e);
-                                    break;
-                                }
+                        let error = match commands::subscribe_to_all(&client, &options).await {
+                            Err(e) => e,
+                            Ok(mut stream) => loop {
+                                match stream.try_next().await {
+                                    Ok(Some(sub_event)) => {
+                                        match &sub_event {
+                                            SubEvent::EventAppeared(event) => {
+                                                attempt = 0;
+                                                offset = StreamPosition::Position(event.get_original_event().position);
+                                            }
 
-                                error!("Subscription: attempt ({}/{}) failure, cause: {}", attempt_count, retry.limit, e);
-                                attempt_count += 1;
-                                tokio::time::sleep(retry.delay).await;
-                            }
-                            Ok(mut stream) => {
-                                loop {
-                                    match stream.try_next().await {
-                                        Ok(sub_event) => {
-                                            let sub_event = sub_event.expect("to be defined");
-                                            match sub_event {
-                                                crate::types::SubEvent::EventAppeared(event) => {
-                                                    offset = crate::types::StreamPosition::Position(event.get_original_event().position);
-                                                    yield Ok(crate::types::SubEvent::EventAppeared(event));
-                                                }
-
-                                                ignored => yield Ok(ignored),
+                                            SubEvent::Checkpoint(position) => {
+                                                attempt = 0;
+                                                offset = StreamPosition::Position(*position);
                                             }
-                                        }
-                                        Err(e) => {
-                                            attempt_count = 1;
-                                            options = options.position(offset);
 
-                                            error!("Subscription dropped cause: {}. Reconnecting", e);
-                                            break;
+                                            _ => {}
                                         }
+
+                                        yield Ok(sub_event);
                                     }
+
+                                    Ok(None) => break crate::Error::ServerError { code: None, message: "Subscription ended by the server".to_string() },
+                                    Err(e) => break e,
                                 }
-                            }
+                            },
+                        };
+
+                        let reason = SubscriptionDropReason::from_error(&error);
+
+                        if !reason.is_recoverable() || !retry.can_retry(attempt) {
+                            error!("Subscription dropped ({:?}), giving up, cause: {}", reason, error);
+
+                            yield Err(crate::Error::SubscriptionDropped { reason, cause: Box::new(error) });
+                            break;
                         }
+
+                        error!("Subscription dropped ({:?}), attempt ({}/{}) to resubscribe, cause: {}", reason, attempt + 1, retry.limit, error);
+                        tokio::time::sleep(retry.delay_for(attempt)).await;
+                        attempt += 1;
+                        options = options.position(offset);
                     }
                 };
 
@@ -333,6 +821,282 @@ impl Client {
         }
     }
 
+    async fn stream_head(&self, stream_name: &str, options: &SubscribeToStreamOptions) -> LiveHead {
+        if let StreamPosition::End = options.position {
+            return LiveHead::Live;
+        }
+
+        let mut read_options = ReadStreamOptions::default()
+            .position(StreamPosition::End)
+            .backwards();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        match self.read_stream(stream_name, &read_options, Single).await {
+            Ok(ReadResult::Ok(Some(event))) => {
+                let head = event.get_original_event().revision;
+
+                match options.position {
+                    StreamPosition::Position(revision) if revision >= head => LiveHead::Live,
+                    _ => LiveHead::Revision(head),
+                }
+            }
+
+            Ok(_) => LiveHead::Live,
+
+            Err(e) => {
+                warn!(
+                    "Couldn't read the head of stream {}, only relying on the server to tell when the subscription is live: {}",
+                    stream_name, e
+                );
+
+                LiveHead::Unknown
+            }
+        }
+    }
+
+    async fn all_head(&self, options: &SubscribeToAllOptions) -> LiveHead {
+        if let StreamPosition::End = options.position {
+            return LiveHead::Live;
+        }
+
+        let mut read_options = ReadAllOptions::default()
+            .position(StreamPosition::End)
+            .backwards();
+
+        // A filtered subscription never sees events past the last matching one.
+        if let Some(filter) = options.filter.clone() {
+            read_options = read_options.filter(filter);
+        }
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        // When few events match the filter, the server may scan most of `$all` backwards.
+        let read = self.read_all(&read_options, Single);
+        let result = if options.filter.is_some() {
+            tokio::time::timeout(FILTERED_HEAD_LOOKUP_TIMEOUT, read)
+                .await
+                .unwrap_or(Err(crate::Error::DeadlineExceeded))
+        } else {
+            read.await
+        };
+
+        match result {
+            Ok(Some(event)) => {
+                let head = event.get_original_event().position;
+
+                match options.position {
+                    StreamPosition::Position(position) if position.commit >= head.commit => {
+                        LiveHead::Live
+                    }
+                    _ => LiveHead::Position(head),
+                }
+            }
+
+            Ok(None) => LiveHead::Live,
+
+            Err(e) => {
+                warn!(
+                    "Couldn't read the head of $all, only relying on the server to tell when the subscription is live: {}",
+                    e
+                );
+
+                LiveHead::Unknown
+            }
+        }
+    }
+
+    /// Like [`subscribe_to_stream`] but resumes from the checkpoint found in the store for the
+    /// given subscription id, and saves checkpoints as events are processed. Without stored
+    /// checkpoint, the subscription starts from `options`' position.
+    ///
+    /// [`subscribe_to_stream`]: #method.subscribe_to_stream
+    pub async fn subscribe_to_stream_with_checkpoints<'a, S>(
+        &self,
+        stream_name: impl AsRef<str>,
+        subscription_id: impl AsRef<str>,
+        store: S,
+        options: &SubscribeToStreamOptions,
+        checkpoint_options: &CheckpointOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>>
+    where
+        S: CheckpointStore + 'a,
+    {
+        let subscription_id = subscription_id.as_ref().to_string();
+        let mut options = options.clone();
+
+        match store.load(subscription_id.as_str()).await? {
+            Some(Checkpoint::Revision(revision)) => {
+                options = options.start_from(StreamPosition::Position(revision));
+            }
+
+            Some(Checkpoint::Position(_)) => {
+                return Err(crate::Error::CheckpointError(format!(
+                    "Subscription {} has a $all checkpoint, not a stream revision",
+                    subscription_id
+                )));
+            }
+
+            None => {}
+        }
+
+        let sub = self.subscribe_to_stream(stream_name, &options).await?;
+
+        Ok(crate::checkpoint::track(
+            sub,
+            store,
+            subscription_id,
+            *checkpoint_options,
+            |event| Checkpoint::Revision(event.get_original_event().revision),
+        ))
+    }
+
+    /// Like [`subscribe_to_all`] but resumes from the checkpoint found in the store for the
+    /// given subscription id, and saves checkpoints as events are processed. Checkpoints sent
+    /// by the server for filtered subscriptions are saved as well.
+    ///
+    /// [`subscribe_to_all`]: #method.subscribe_to_all
+    pub async fn subscribe_to_all_with_checkpoints<'a, S>(
+        &self,
+        subscription_id: impl AsRef<str>,
+        store: S,
+        options: &SubscribeToAllOptions,
+        checkpoint_options: &CheckpointOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>>
+    where
+        S: CheckpointStore + 'a,
+    {
+        let subscription_id = subscription_id.as_ref().to_string();
+        let mut options = options.clone();
+
+        match store.load(subscription_id.as_str()).await? {
+            Some(Checkpoint::Position(position)) => {
+                options = options.position(StreamPosition::Position(position));
+            }
+
+            Some(Checkpoint::Revision(_)) => {
+                return Err(crate::Error::CheckpointError(format!(
+                    "Subscription {} has a stream revision checkpoint, not a $all position",
+                    subscription_id
+                )));
+            }
+
+            None => {}
+        }
+
+        let sub = self.subscribe_to_all(&options).await?;
+
+        Ok(crate::checkpoint::track(
+            sub,
+            store,
+            subscription_id,
+            *checkpoint_options,
+            |event| Checkpoint::Position(event.get_original_event().position),
+        ))
+    }
+
+    /// Reads the `$ce-{category}` stream, linking to the events of every stream of a category.
+    /// Links are resolved: `ResolvedEvent::event` is the target event and `ResolvedEvent::link`
+    /// the link. Requires the `$by_category` system projection.
+    pub async fn read_category<Count>(
+        &self,
+        category: impl AsRef<str>,
+        options: &ReadStreamOptions,
+        count: Count,
+    ) -> crate::Result<ReadResult<Count::Selection>>
+    where
+        Count: ToCount<'static>,
+    {
+        let options = options.clone().resolve_link_tos();
+
+        self.read_stream(StreamName::category_stream(category), &options, count)
+            .await
+    }
+
+    /// Reads the `$et-{event_type}` stream, linking to every event of a given type. Links are
+    /// resolved. Requires the `$by_event_type` system projection.
+    pub async fn read_event_type<Count>(
+        &self,
+        event_type: impl AsRef<str>,
+        options: &ReadStreamOptions,
+        count: Count,
+    ) -> crate::Result<ReadResult<Count::Selection>>
+    where
+        Count: ToCount<'static>,
+    {
+        let options = options.clone().resolve_link_tos();
+
+        self.read_stream(StreamName::event_type_stream(event_type), &options, count)
+            .await
+    }
+
+    /// Reads the `$bc-{correlation_id}` stream, linking to every event sharing a correlation id.
+    /// Links are resolved. Requires the `$by_correlation_id` system projection.
+    pub async fn read_correlation<Count>(
+        &self,
+        correlation_id: impl AsRef<str>,
+        options: &ReadStreamOptions,
+        count: Count,
+    ) -> crate::Result<ReadResult<Count::Selection>>
+    where
+        Count: ToCount<'static>,
+    {
+        let options = options.clone().resolve_link_tos();
+
+        self.read_stream(
+            StreamName::correlation_stream(correlation_id),
+            &options,
+            count,
+        )
+        .await
+    }
+
+    /// Like [`read_category`] but subscribes to the category stream. Links are resolved.
+    ///
+    /// [`read_category`]: #method.read_category
+    pub async fn subscribe_to_category<'a>(
+        &self,
+        category: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
+        let options = options.clone().resolve_link_tos();
+
+        self.subscribe_to_stream(StreamName::category_stream(category), &options)
+            .await
+    }
+
+    /// Like [`read_event_type`] but subscribes to the event-type stream. Links are resolved.
+    ///
+    /// [`read_event_type`]: #method.read_event_type
+    pub async fn subscribe_to_event_type<'a>(
+        &self,
+        event_type: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
+        let options = options.clone().resolve_link_tos();
+
+        self.subscribe_to_stream(StreamName::event_type_stream(event_type), &options)
+            .await
+    }
+
+    /// Like [`read_correlation`] but subscribes to the correlation stream. Links are resolved.
+    ///
+    /// [`read_correlation`]: #method.read_correlation
+    pub async fn subscribe_to_correlation<'a>(
+        &self,
+        correlation_id: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>> {
+        let options = options.clone().resolve_link_tos();
+
+        self.subscribe_to_stream(StreamName::correlation_stream(correlation_id), &options)
+            .await
+    }
+
     /// Creates a persistent subscription group on a stream.
     ///
     /// Persistent subscriptions are special kind of subscription where the
@@ -616,9 +1380,12 @@ async fn http_execute_request(
     let resp = builder.send().await.map_err(|e| {
         if let Some(status) = e.status() {
             match status {
-                http::StatusCode::UNAUTHORIZED => crate::Error::AccessDenied,
+                http::StatusCode::UNAUTHORIZED => crate::Error::AccessDenied { code: None },
                 http::StatusCode::NOT_FOUND => crate::Error::ResourceNotFound,
-                code if code.is_server_error() => crate::Error::ServerError(e.to_string()),
+                code if code.is_server_error() => crate::Error::ServerError {
+                    code: None,
+                    message: e.to_string(),
+                },
                 code => {
                     error!(
                         "Unexpected error when dealing with HTTP request to the server: Code={:?}, {}",
@@ -646,12 +1413,15 @@ async fn http_execute_request(
     let msg = resp.text().await.unwrap_or_else(|_| "".to_string());
 
     match code {
-        http::StatusCode::UNAUTHORIZED => Err(crate::Error::AccessDenied),
+        http::StatusCode::UNAUTHORIZED => Err(crate::Error::AccessDenied { code: None }),
         http::StatusCode::NOT_FOUND => Err(crate::Error::ResourceNotFound),
-        code if code.is_server_error() => Err(crate::Error::ServerError(format!(
-            "unexpected server error, reason: {:?}",
-            code.canonical_reason()
-        ))),
+        code if code.is_server_error() => Err(crate::Error::ServerError {
+            code: None,
+            message: format!(
+                "unexpected server error, reason: {:?}",
+                code.canonical_reason()
+            ),
+        }),
         code => {
             error!(
                 "Unexpected error when dealing with HTTP request to the server: Code={:?}: {}",
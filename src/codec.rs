@@ -0,0 +1,533 @@
+//! Payload codecs used to encode and decode event data.
+//!
+//! Every codec is associated to a `content-type`. When an event is created with
+//! [`EventData::encode_with`], that `content-type` is stored along with the event so consumers
+//! can later pick the right codec with [`RecordedEvent::decode_with`].
+//!
+//! The server only remembers whether an event is JSON. Any other `content-type` than
+//! `application/json` and `application/octet-stream` is persisted under the
+//! [`CONTENT_TYPE_METADATA_KEY`] key of the event custom metadata, which must then be a JSON
+//! object. Custom metadata that isn't a JSON object is wrapped in one. A marker key is written
+//! alongside, so that both are transparently undone when the event is read back while custom
+//! metadata written by other means is left untouched.
+//!
+//! [`EventData::encode_with`]: crate::EventData::encode_with
+//! [`RecordedEvent::decode_with`]: crate::RecordedEvent::decode_with
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use thiserror::Error;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+pub const MESSAGE_PACK_CONTENT_TYPE: &str = "application/msgpack";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Custom metadata key holding the `content-type` of an event, when the server can't keep it.
+pub const CONTENT_TYPE_METADATA_KEY: &str = "$contentType";
+
+// Custom metadata key holding base64 encoded custom metadata that wasn't a JSON object.
+const WRAPPED_METADATA_KEY: &str = "$customMetadata";
+
+// Custom metadata key marking that the `content-type` was persisted by this module.
+const PERSISTED_MARKER_KEY: &str = "$contentTypePersisted";
+
+/// Returns the custom metadata to send to the server, so the event `content-type` survives being
+/// written.
+pub(crate) fn persist_content_type(
+    metadata: &HashMap<String, String>,
+    custom_metadata: Option<Bytes>,
+) -> Option<Bytes> {
+    let content_type = match metadata.get("content-type") {
+        Some(content_type)
+            if content_type != JSON_CONTENT_TYPE && content_type != OCTET_STREAM_CONTENT_TYPE =>
+        {
+            content_type
+        }
+
+        _ => return custom_metadata,
+    };
+
+    let mut object = match custom_metadata.as_deref() {
+        None | Some(b"") => Map::new(),
+        Some(bytes) => match serde_json::from_slice::<Value>(bytes) {
+            Ok(Value::Object(object))
+                if ![
+                    CONTENT_TYPE_METADATA_KEY,
+                    WRAPPED_METADATA_KEY,
+                    PERSISTED_MARKER_KEY,
+                ]
+                .iter()
+                .any(|key| object.contains_key(*key)) =>
+            {
+                object
+            }
+
+            _ => {
+                let mut object = Map::new();
+                object.insert(
+                    WRAPPED_METADATA_KEY.to_string(),
+                    Value::String(base64::encode(bytes)),
+                );
+
+                object
+            }
+        },
+    };
+
+    object.insert(
+        CONTENT_TYPE_METADATA_KEY.to_string(),
+        Value::String(content_type.clone()),
+    );
+    object.insert(PERSISTED_MARKER_KEY.to_string(), Value::Bool(true));
+
+    Some(Bytes::from(
+        serde_json::to_vec(&object).expect("a JSON object always serializes"),
+    ))
+}
+
+/// Restores the `content-type` persisted by [`persist_content_type`] into the event metadata and
+/// returns the custom metadata as it was before being sent. Custom metadata that wasn't written
+/// by [`persist_content_type`] is returned as is.
+pub(crate) fn restore_content_type(
+    metadata: &mut HashMap<String, String>,
+    custom_metadata: Bytes,
+) -> Bytes {
+    let mut object = match serde_json::from_slice::<Value>(&custom_metadata) {
+        Ok(Value::Object(object))
+            if object.get(PERSISTED_MARKER_KEY) == Some(&Value::Bool(true)) =>
+        {
+            object
+        }
+        _ => return custom_metadata,
+    };
+
+    object.remove(PERSISTED_MARKER_KEY);
+
+    let content_type = match object.remove(CONTENT_TYPE_METADATA_KEY) {
+        Some(Value::String(content_type)) => content_type,
+        _ => return custom_metadata,
+    };
+
+    let restored = match object.remove(WRAPPED_METADATA_KEY) {
+        Some(Value::String(wrapped)) => match base64::decode(wrapped) {
+            Ok(bytes) => Bytes::from(bytes),
+            Err(_) => return custom_metadata,
+        },
+
+        _ if object.is_empty() => Bytes::new(),
+        _ => Bytes::from(serde_json::to_vec(&object).expect("a JSON object always serializes")),
+    };
+
+    metadata.insert("content-type".to_string(), content_type);
+
+    restored
+}
+
+#[derive(Error, Debug, Clone)]
+/// Error raised when encoding or decoding an event payload.
+pub enum CodecError {
+    #[error("Failed to encode payload as {content_type}: {message}")]
+    Encode {
+        content_type: String,
+        message: String,
+    },
+    #[error("Failed to decode payload as {content_type}: {message}")]
+    Decode {
+        content_type: String,
+        message: String,
+    },
+    #[error("No codec registered for content-type: {0}")]
+    UnsupportedContentType(String),
+    #[error("Event has no content-type")]
+    MissingContentType,
+}
+
+impl CodecError {
+    pub(crate) fn encode(content_type: &str, message: impl ToString) -> Self {
+        CodecError::Encode {
+            content_type: content_type.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn decode(content_type: &str, message: impl ToString) -> Self {
+        CodecError::Decode {
+            content_type: content_type.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Encodes and decodes payloads of type `A` for a given `content-type`.
+pub trait Codec<A>: Send + Sync {
+    /// `content-type` stored in the event metadata.
+    fn content_type(&self) -> &str;
+
+    /// Encodes a value into an event payload.
+    fn encode(&self, value: &A) -> Result<Bytes, CodecError>;
+
+    /// Decodes an event payload.
+    fn decode(&self, payload: &[u8]) -> Result<A, CodecError>;
+}
+
+/// Selects the codec to use according to an event `content-type`. Codecs provided by this
+/// crate are also a `CodecSet` which only supports their own `content-type`. Use [`Codecs`]
+/// when several `content-type` need to be supported.
+pub trait CodecSet<A> {
+    fn codec_for(&self, content_type: &str) -> Option<&dyn Codec<A>>;
+}
+
+macro_rules! single_codec_set {
+    ($codec:ty, $($bound:tt)+) => {
+        impl<A> CodecSet<A> for $codec
+        where
+            A: $($bound)+,
+        {
+            fn codec_for(&self, content_type: &str) -> Option<&dyn Codec<A>> {
+                if Codec::<A>::content_type(self) == content_type {
+                    return Some(self);
+                }
+
+                None
+            }
+        }
+    };
+}
+
+/// A collection of codecs, dispatching on the `content-type` of an event.
+///
+/// ```
+/// # use eventstore::{Codecs, Json};
+/// # #[derive(serde::Serialize, serde::Deserialize)]
+/// # struct Foo;
+/// let codecs = Codecs::<Foo>::new().with(Json);
+/// ```
+pub struct Codecs<A> {
+    inner: Vec<Box<dyn Codec<A>>>,
+}
+
+impl<A> Default for Codecs<A> {
+    fn default() -> Self {
+        Self { inner: Vec::new() }
+    }
+}
+
+impl<A> Codecs<A> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a codec. If a codec was already registered for the same `content-type`, the
+    /// first registered codec wins.
+    pub fn with<C>(mut self, codec: C) -> Self
+    where
+        C: Codec<A> + 'static,
+    {
+        self.inner.push(Box::new(codec));
+        self
+    }
+}
+
+impl<A> CodecSet<A> for Codecs<A> {
+    fn codec_for(&self, content_type: &str) -> Option<&dyn Codec<A>> {
+        self.inner
+            .iter()
+            .find(|codec| codec.content_type() == content_type)
+            .map(|codec| codec.as_ref())
+    }
+}
+
+/// JSON codec, based on `serde_json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl<A> Codec<A> for Json
+where
+    A: Serialize + DeserializeOwned,
+{
+    fn content_type(&self) -> &str {
+        JSON_CONTENT_TYPE
+    }
+
+    fn encode(&self, value: &A) -> Result<Bytes, CodecError> {
+        serde_json::to_vec(value)
+            .map(Bytes::from)
+            .map_err(|e| CodecError::encode(JSON_CONTENT_TYPE, e))
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<A, CodecError> {
+        serde_json::from_slice(payload).map_err(|e| CodecError::decode(JSON_CONTENT_TYPE, e))
+    }
+}
+
+single_codec_set!(Json, Serialize + DeserializeOwned);
+
+/// Protocol Buffers codec, based on `prost`.
+#[cfg(feature = "protobuf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Protobuf;
+
+#[cfg(feature = "protobuf")]
+impl<A> Codec<A> for Protobuf
+where
+    A: prost::Message + Default,
+{
+    fn content_type(&self) -> &str {
+        PROTOBUF_CONTENT_TYPE
+    }
+
+    fn encode(&self, value: &A) -> Result<Bytes, CodecError> {
+        let mut buf = Vec::with_capacity(value.encoded_len());
+
+        value
+            .encode(&mut buf)
+            .map_err(|e| CodecError::encode(PROTOBUF_CONTENT_TYPE, e))?;
+
+        Ok(buf.into())
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<A, CodecError> {
+        A::decode(payload).map_err(|e| CodecError::decode(PROTOBUF_CONTENT_TYPE, e))
+    }
+}
+
+#[cfg(feature = "protobuf")]
+single_codec_set!(Protobuf, prost::Message + Default);
+
+/// MessagePack codec, based on `rmp-serde`.
+#[cfg(feature = "msgpack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl<A> Codec<A> for MessagePack
+where
+    A: Serialize + DeserializeOwned,
+{
+    fn content_type(&self) -> &str {
+        MESSAGE_PACK_CONTENT_TYPE
+    }
+
+    fn encode(&self, value: &A) -> Result<Bytes, CodecError> {
+        rmp_serde::to_vec_named(value)
+            .map(Bytes::from)
+            .map_err(|e| CodecError::encode(MESSAGE_PACK_CONTENT_TYPE, e))
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<A, CodecError> {
+        rmp_serde::from_slice(payload).map_err(|e| CodecError::decode(MESSAGE_PACK_CONTENT_TYPE, e))
+    }
+}
+
+#[cfg(feature = "msgpack")]
+single_codec_set!(MessagePack, Serialize + DeserializeOwned);
+
+/// CBOR codec, based on `ciborium`.
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl<A> Codec<A> for Cbor
+where
+    A: Serialize + DeserializeOwned,
+{
+    fn content_type(&self) -> &str {
+        CBOR_CONTENT_TYPE
+    }
+
+    fn encode(&self, value: &A) -> Result<Bytes, CodecError> {
+        let mut buf = Vec::new();
+
+        ciborium::ser::into_writer(value, &mut buf)
+            .map_err(|e| CodecError::encode(CBOR_CONTENT_TYPE, e))?;
+
+        Ok(buf.into())
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<A, CodecError> {
+        ciborium::de::from_reader(payload).map_err(|e| CodecError::decode(CBOR_CONTENT_TYPE, e))
+    }
+}
+
+#[cfg(feature = "cbor")]
+single_codec_set!(Cbor, Serialize + DeserializeOwned);
+
+#[cfg(test)]
+mod codec_tests {
+    use super::{
+        persist_content_type, restore_content_type, CodecError, CodecSet, Codecs, Json,
+        JSON_CONTENT_TYPE, OCTET_STREAM_CONTENT_TYPE,
+    };
+    use crate::{EventData, Position, RecordedEvent};
+    use bytes::Bytes;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Foo {
+        value: u32,
+    }
+
+    // Mimics a write and a read, the server only keeping whether the event is JSON.
+    fn recorded(event: EventData) -> RecordedEvent {
+        let custom_metadata =
+            persist_content_type(&event.metadata, event.custom_metadata).unwrap_or_default();
+        let is_json =
+            event.metadata.get("content-type").map(String::as_str) == Some(JSON_CONTENT_TYPE);
+        let mut metadata = event.metadata;
+
+        metadata.insert(
+            "content-type".to_string(),
+            if is_json {
+                JSON_CONTENT_TYPE
+            } else {
+                OCTET_STREAM_CONTENT_TYPE
+            }
+            .to_string(),
+        );
+
+        let custom_metadata = restore_content_type(&mut metadata, custom_metadata);
+
+        RecordedEvent {
+            stream_id: "foo".to_string(),
+            id: uuid::Uuid::new_v4(),
+            revision: 0,
+            event_type: metadata.get("type").cloned().unwrap(),
+            is_json,
+            data: event.payload,
+            metadata,
+            custom_metadata,
+            position: Position::start(),
+        }
+    }
+
+    #[test]
+    fn json_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = Foo { value: 42 };
+        let event = EventData::encode_with("foo-event", &Json, &expected)?;
+
+        assert_eq!(
+            event.metadata.get("content-type").map(String::as_str),
+            Some(JSON_CONTENT_TYPE)
+        );
+
+        let actual: Foo = recorded(event).decode_with(&Json)?;
+
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[test]
+    fn persist_content_type_in_custom_metadata() {
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("content-type".to_string(), "application/cbor".to_string());
+
+        for custom_metadata in [
+            None,
+            Some(Bytes::from_static(br#"{"correlation":"abc"}"#)),
+            Some(Bytes::from_static(b"\x00raw")),
+            Some(Bytes::from_static(br#"{"$contentType":"text/plain"}"#)),
+        ] {
+            let sent = persist_content_type(&metadata, custom_metadata.clone()).unwrap();
+            let mut read_metadata = std::collections::HashMap::new();
+            read_metadata.insert(
+                "content-type".to_string(),
+                OCTET_STREAM_CONTENT_TYPE.to_string(),
+            );
+
+            let restored = restore_content_type(&mut read_metadata, sent);
+
+            assert_eq!(restored, custom_metadata.unwrap_or_default());
+            assert_eq!(read_metadata, metadata);
+        }
+
+        metadata.insert("content-type".to_string(), JSON_CONTENT_TYPE.to_string());
+
+        assert_eq!(persist_content_type(&metadata, None), None);
+    }
+
+    #[test]
+    fn leave_foreign_custom_metadata_untouched() {
+        let custom_metadata = Bytes::from_static(br#"{"$contentType":"text/plain","foo":1}"#);
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(
+            "content-type".to_string(),
+            OCTET_STREAM_CONTENT_TYPE.to_string(),
+        );
+
+        let restored = restore_content_type(&mut metadata, custom_metadata.clone());
+
+        assert_eq!(restored, custom_metadata);
+        assert_eq!(
+            metadata.get("content-type").map(String::as_str),
+            Some(OCTET_STREAM_CONTENT_TYPE)
+        );
+    }
+
+    #[test]
+    fn unsupported_content_type() {
+        let event = EventData::binary("foo-event", "raw".into());
+        let codecs = Codecs::<Foo>::new().with(Json);
+
+        assert!(codecs.codec_for(JSON_CONTENT_TYPE).is_some());
+        assert!(matches!(
+            recorded(event).decode_with(&codecs),
+            Err(CodecError::UnsupportedContentType(_))
+        ));
+    }
+
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    #[test]
+    fn dispatch_on_content_type() -> Result<(), Box<dyn std::error::Error>> {
+        use super::{Cbor, MessagePack};
+
+        let codecs = Codecs::<Foo>::new().with(Json).with(MessagePack).with(Cbor);
+        let expected = Foo { value: 42 };
+
+        for event in [
+            EventData::encode_with("foo-event", &MessagePack, &expected)?,
+            EventData::encode_with("foo-event", &Cbor, &expected)?,
+        ] {
+            let actual: Foo = recorded(event).decode_with(&codecs)?;
+            assert_eq!(expected, actual);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn protobuf_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        use super::{Protobuf, PROTOBUF_CONTENT_TYPE};
+        use crate::event_store::client::shared::StreamIdentifier;
+        use std::collections::HashMap;
+
+        let expected = StreamIdentifier {
+            stream_name: b"foo".to_vec(),
+        };
+
+        let event = EventData::encode_with("foo-event", &Protobuf, &expected)?;
+        let mut metadata = HashMap::new();
+        metadata.insert("type".to_string(), "foo-event".to_string());
+        metadata.insert(
+            "content-type".to_string(),
+            PROTOBUF_CONTENT_TYPE.to_string(),
+        );
+
+        assert_eq!(event.metadata, metadata);
+
+        let actual: StreamIdentifier = recorded(event).decode_with(&Protobuf)?;
+
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+}
@@ -14,7 +14,7 @@ use shared::{Empty, StreamIdentifier, Uuid};
 use streams::streams_client::StreamsClient;
 
 use crate::batch::BatchAppendClient;
-use crate::grpc::GrpcClient;
+use crate::grpc::{GrpcClient, Handle};
 use crate::options::append_to_stream::AppendToStreamOptions;
 use crate::options::batch_append::BatchAppendOptions;
 use crate::options::persistent_subscription::PersistentSubscriptionOptions;
@@ -27,6 +27,7 @@ use crate::{
     SubscribeToAllOptions, SubscribeToPersistentSubscriptionn, SubscriptionFilter,
     SystemConsumerStrategy, TombstoneStreamOptions,
 };
+use futures::future::{BoxFuture, Either};
 use futures::stream::BoxStream;
 use tonic::Request;
 
@@ -88,9 +89,9 @@ fn convert_event_data(event: EventData) -> streams::AppendReq {
     let id = event.id_opt.unwrap_or_else(uuid::Uuid::new_v4);
     let id = shared::uuid::Value::String(id.to_string());
     let id = Uuid { value: Some(id) };
-    let custom_metadata = event
-        .custom_metadata
-        .map_or_else(Vec::new, |b| (&*b).into());
+    let custom_metadata =
+        crate::codec::persist_content_type(&event.metadata, event.custom_metadata)
+            .map_or_else(Vec::new, |b| (&*b).into());
 
     let msg = append_req::ProposedMessage {
         id: Some(id),
@@ -138,6 +139,10 @@ fn convert_proto_recorded_event(
             .stream_name,
     )
     .expect("It's always UTF-8");
+    let mut metadata = event.metadata;
+    let custom_metadata =
+        crate::codec::restore_content_type(&mut metadata, event.custom_metadata.into());
+
     RecordedEvent {
         id,
         stream_id,
@@ -145,8 +150,8 @@ fn convert_proto_recorded_event(
         position,
         event_type,
         is_json,
-        metadata: event.metadata,
-        custom_metadata: event.custom_metadata.into(),
+        metadata,
+        custom_metadata,
         data: event.data.into(),
     }
 }
@@ -184,6 +189,10 @@ fn convert_persistent_proto_recorded_event(
     )
     .expect("string is UTF-8 valid");
 
+    let mut metadata = event.metadata;
+    let custom_metadata =
+        crate::codec::restore_content_type(&mut metadata, event.custom_metadata.into());
+
     RecordedEvent {
         id,
         stream_id,
@@ -191,8 +200,8 @@ fn convert_persistent_proto_recorded_event(
         position,
         event_type,
         is_json,
-        metadata: event.metadata,
-        custom_metadata: event.custom_metadata.into(),
+        metadata,
+        custom_metadata,
         data: event.data.into(),
     }
 }
@@ -205,9 +214,9 @@ fn convert_event_data_to_batch_proposed_message(
     let id = event.id_opt.unwrap_or_else(uuid::Uuid::new_v4);
     let id = shared::uuid::Value::String(id.to_string());
     let id = Uuid { value: Some(id) };
-    let custom_metadata = event
-        .custom_metadata
-        .map_or_else(Vec::new, |b| (&*b).into());
+    let custom_metadata =
+        crate::codec::persist_content_type(&event.metadata, event.custom_metadata)
+            .map_or_else(Vec::new, |b| (&*b).into());
 
     batch_append_req::ProposedMessage {
         id: Some(id),
@@ -217,6 +226,111 @@ fn convert_event_data_to_batch_proposed_message(
     }
 }
 
+/// Decodes the `google.rpc.Status` of a failed batch-append request, based on its code and the
+/// EventStoreDB message carried in its details.
+fn convert_batch_append_error(
+    stream_name: String,
+    status: crate::google::rpc::Status,
+) -> crate::batch::BatchAppendError {
+    use crate::batch::BatchAppendError;
+    use prost::Message;
+    use shared::wrong_expected_version::{
+        CurrentStreamRevisionOption, ExpectedStreamPositionOption,
+    };
+
+    let code = tonic::Code::from(status.code);
+    let details = match status.details {
+        Some(details) => details,
+        None => {
+            return match code {
+                tonic::Code::PermissionDenied | tonic::Code::Unauthenticated => {
+                    BatchAppendError::AccessDenied
+                }
+                tonic::Code::DeadlineExceeded => BatchAppendError::Timeout,
+                _ => BatchAppendError::Other {
+                    code,
+                    message: status.message,
+                },
+            }
+        }
+    };
+
+    let type_name = details
+        .type_url
+        .rsplit(['/', '.'])
+        .next()
+        .unwrap_or_default();
+    let value = details.value.as_slice();
+
+    let decoded = match type_name {
+        "WrongExpectedVersion" => shared::WrongExpectedVersion::decode(value).map(|wev| {
+            let current = match wev.current_stream_revision_option {
+                Some(CurrentStreamRevisionOption::CurrentStreamRevision(rev)) => {
+                    CurrentRevision::Current(rev)
+                }
+                Some(CurrentStreamRevisionOption::CurrentNoStream(_)) | None => {
+                    CurrentRevision::NoStream
+                }
+            };
+
+            let expected = match wev.expected_stream_position_option {
+                Some(ExpectedStreamPositionOption::ExpectedStreamPosition(rev)) => {
+                    ExpectedRevision::Exact(rev)
+                }
+                Some(ExpectedStreamPositionOption::ExpectedNoStream(_)) => {
+                    ExpectedRevision::NoStream
+                }
+                Some(ExpectedStreamPositionOption::ExpectedStreamExists(_)) => {
+                    ExpectedRevision::StreamExists
+                }
+                Some(ExpectedStreamPositionOption::ExpectedAny(_)) | None => ExpectedRevision::Any,
+            };
+
+            BatchAppendError::WrongExpectedVersion(WrongExpectedVersion { current, expected })
+        }),
+
+        "StreamDeleted" => shared::StreamDeleted::decode(value).map(|deleted| {
+            let stream_name = deleted
+                .stream_identifier
+                .and_then(|ident| String::from_utf8(ident.stream_name).ok())
+                .unwrap_or(stream_name);
+
+            BatchAppendError::StreamDeleted { stream_name }
+        }),
+
+        "AccessDenied" => Ok(BatchAppendError::AccessDenied),
+
+        "MaximumAppendSizeExceeded" => {
+            shared::MaximumAppendSizeExceeded::decode(value).map(|exceeded| {
+                BatchAppendError::MaximumAppendSizeExceeded {
+                    max_append_size: exceeded.max_append_size,
+                }
+            })
+        }
+
+        "Timeout" => Ok(BatchAppendError::Timeout),
+
+        "InvalidTransaction" => Ok(BatchAppendError::InvalidTransaction),
+
+        "BadRequest" => shared::BadRequest::decode(value)
+            .map(|bad_request| BatchAppendError::BadRequest(bad_request.message)),
+
+        _ => Ok(BatchAppendError::Other {
+            code,
+            message: status.message.clone(),
+        }),
+    };
+
+    decoded.unwrap_or_else(|e| {
+        warn!("Failed to decode batch-append error details: {}", e);
+
+        BatchAppendError::Other {
+            code,
+            message: status.message,
+        }
+    })
+}
+
 /// This trait is added as a compatibility layer when interacting with EventStoreDB servers <= 21 version.
 /// Its goal is to translate a persistent subscription starting position value to a u64 so the deprecated
 /// revision field can be used.
@@ -330,7 +444,10 @@ where
     }
 }
 
-fn convert_proto_read_event(event: streams::read_resp::ReadEvent) -> ResolvedEvent {
+fn convert_proto_read_event(
+    event: streams::read_resp::ReadEvent,
+    resolve_link_tos: bool,
+) -> ResolvedEvent {
     let commit_position = if let Some(pos_alt) = event.position {
         match pos_alt {
             streams::read_resp::read_event::Position::CommitPosition(pos) => Some(pos),
@@ -340,11 +457,20 @@ fn convert_proto_read_event(event: streams::read_resp::ReadEvent) -> ResolvedEve
         None
     };
 
-    ResolvedEvent {
+    let mut event = ResolvedEvent {
         event: event.event.map(convert_proto_recorded_event),
         link: event.link.map(convert_proto_recorded_event),
         commit_position,
+    };
+
+    // A link whose target is gone can come back as a bare link. It's moved to the link slot, so
+    // it is reported as broken instead of passing for a regular event.
+    if resolve_link_tos && event.link.is_none() && event.event.as_ref().is_some_and(|e| e.is_link())
+    {
+        event.link = event.event.take();
     }
+
+    event
 }
 
 fn convert_persistent_proto_read_event(
@@ -449,96 +575,214 @@ pub fn ps_create_filter_into_proto(
     }
 }
 
-/// Sends asynchronously the write command to the server.
+/// Sends asynchronously the write command to the server. Unless `buffered`, events are sent as
+/// they are produced and the append isn't retried.
 pub async fn append_to_stream<S, Events>(
     connection: &GrpcClient,
     stream: S,
     options: &AppendToStreamOptions,
     events: Events,
+    buffered: bool,
 ) -> crate::Result<Result<WriteResult, WrongExpectedVersion>>
 where
     S: AsRef<str>,
     Events: Stream<Item = EventData> + Send + Sync + 'static,
+{
+    let stream = stream.as_ref();
+    let credentials = options
+        .credentials
+        .clone()
+        .or_else(|| connection.default_credentials());
+
+    if !buffered || connection.retry_policy(options.retry.as_ref()).is_none() {
+        return connection
+            .execute(|handle| append_request(handle, stream, options, credentials, events))
+            .await;
+    }
+
+    let events = events.collect::<Vec<_>>().await;
+
+    // Without explicit ids, a retry could write the events twice if the first attempt went
+    // through before the error.
+    if events.iter().any(|event| event.id_opt.is_none()) {
+        return connection
+            .execute(|handle| {
+                append_request(handle, stream, options, credentials, stream::iter(events))
+            })
+            .await;
+    }
+
+    connection
+        .execute_with_retry(options.retry.as_ref(), |handle| {
+            append_request(
+                handle,
+                stream,
+                options,
+                credentials.clone(),
+                stream::iter(events.clone()),
+            )
+        })
+        .await
+}
+
+async fn append_request<Events>(
+    handle: Handle,
+    stream: &str,
+    options: &AppendToStreamOptions,
+    credentials: Option<Credentials>,
+    events: Events,
+) -> Result<Result<WriteResult, WrongExpectedVersion>, tonic::Status>
+where
+    Events: Stream<Item = EventData> + Send + Sync + 'static,
 {
     use streams::append_req::{self, Content};
     use streams::AppendReq;
 
-    let stream = stream.as_ref().to_string();
+    let stream_identifier = Some(StreamIdentifier {
+        stream_name: stream.to_string().into_bytes(),
+    });
+    let header = Content::Options(append_req::Options {
+        stream_identifier,
+        expected_stream_revision: Some(options.version.clone()),
+    });
+    let header = AppendReq {
+        content: Some(header),
+    };
+    let header = stream::once(async move { header });
+    let events = events.map(convert_event_data);
+    let payload = header.chain(events);
+    let mut req = Request::new(payload);
 
-    connection.execute(move |channel| async move {
-        let stream_identifier = Some(StreamIdentifier {
-            stream_name: stream.into_bytes(),
-        });
-        let header = Content::Options(append_req::Options {
-            stream_identifier,
-            expected_stream_revision: Some(options.version.clone()),
-        });
-        let header = AppendReq {
-            content: Some(header),
-        };
-        let header = stream::once(async move { header });
-        let events = events.map(convert_event_data);
-        let payload = header.chain(events);
-        let mut req = Request::new(payload);
+    configure_auth_req(&mut req, credentials);
 
-        let credentials = options.credentials.clone().or_else(|| connection.default_credentials());
+    let mut client = StreamsClient::new(handle.channel);
+    let resp = client.append(req).await?.into_inner();
 
-        configure_auth_req(&mut req, credentials);
+    match resp.result.unwrap() {
+        streams::append_resp::Result::Success(success) => {
+            let next_expected_version = match success.current_revision_option.unwrap() {
+                streams::append_resp::success::CurrentRevisionOption::CurrentRevision(rev) => rev,
+                streams::append_resp::success::CurrentRevisionOption::NoStream(_) => 0,
+            };
 
-        let mut client = StreamsClient::new(channel.channel);
-        let resp = client.append(req).await?.into_inner();
+            let position = match success.position_option.unwrap() {
+                streams::append_resp::success::PositionOption::Position(pos) => Position {
+                    commit: pos.commit_position,
+                    prepare: pos.prepare_position,
+                },
 
-        match resp.result.unwrap() {
-            streams::append_resp::Result::Success(success) => {
-                let next_expected_version = match success.current_revision_option.unwrap() {
-                    streams::append_resp::success::CurrentRevisionOption::CurrentRevision(rev) => {
-                        rev
-                    }
-                    streams::append_resp::success::CurrentRevisionOption::NoStream(_) => 0,
-                };
+                streams::append_resp::success::PositionOption::NoPosition(_) => Position::start(),
+            };
 
-                let position = match success.position_option.unwrap() {
-                    streams::append_resp::success::PositionOption::Position(pos) => Position {
-                        commit: pos.commit_position,
-                        prepare: pos.prepare_position,
-                    },
+            let write_result = WriteResult {
+                next_expected_version,
+                position,
+            };
 
-                    streams::append_resp::success::PositionOption::NoPosition(_) => {
-                        Position::start()
-                    }
-                };
+            Ok(Ok(write_result))
+        }
 
-                let write_result = WriteResult {
-                    next_expected_version,
-                    position,
-                };
+        streams::append_resp::Result::WrongExpectedVersion(error) => {
+            let current = match error.current_revision_option.unwrap() {
+                streams::append_resp::wrong_expected_version::CurrentRevisionOption::CurrentRevision(rev) => CurrentRevision::Current(rev),
+                streams::append_resp::wrong_expected_version::CurrentRevisionOption::CurrentNoStream(_) => CurrentRevision::NoStream,
+            };
 
-                Ok(Ok(write_result))
-            }
+            let expected = match error.expected_revision_option.unwrap() {
+                streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedRevision(rev) => ExpectedRevision::Exact(rev),
+                streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedAny(_) => ExpectedRevision::Any,
+                streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedStreamExists(_) => ExpectedRevision::StreamExists,
+                streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedNoStream(_) => ExpectedRevision::NoStream,
+            };
 
-            streams::append_resp::Result::WrongExpectedVersion(error) => {
-                let current = match error.current_revision_option.unwrap() {
-                    streams::append_resp::wrong_expected_version::CurrentRevisionOption::CurrentRevision(rev) => CurrentRevision::Current(rev),
-                    streams::append_resp::wrong_expected_version::CurrentRevisionOption::CurrentNoStream(_) => CurrentRevision::NoStream,
-                };
+            Ok(Err(WrongExpectedVersion { current, expected }))
+        }
+    }
+}
 
-                let expected = match error.expected_revision_option.unwrap() {
-                    streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedRevision(rev) => ExpectedRevision::Exact(rev),
-                    streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedAny(_) => ExpectedRevision::Any,
-                    streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedStreamExists(_) => ExpectedRevision::StreamExists,
-                    streams::append_resp::wrong_expected_version::ExpectedRevisionOption::ExpectedNoStream(_) => ExpectedRevision::NoStream,
-                };
+/// Appends events in several appends, each of them staying under the `chunk_size` byte budget.
+pub async fn append_to_stream_in_chunks<S, Events>(
+    connection: &GrpcClient,
+    stream: S,
+    options: &AppendToStreamOptions,
+    chunk_size: usize,
+    events: Events,
+) -> crate::Result<Result<WriteResult, WrongExpectedVersion>>
+where
+    S: AsRef<str>,
+    Events: Stream<Item = EventData> + Send + Sync + 'static,
+{
+    let stream = stream.as_ref();
+    let mut events = Box::pin(events);
+    let mut options = options.clone();
+    let mut chunker = crate::batch::Chunker::new(chunk_size);
+
+    loop {
+        let (chunk, last) = match events.next().await {
+            Some(event) => match chunker.push(event) {
+                Some(chunk) => (chunk, false),
+                None => continue,
+            },
+
+            None => (chunker.take(), true),
+        };
 
-                Ok(Err(WrongExpectedVersion { current, expected }))
-            }
+        let result = match append_to_stream(connection, stream, &options, stream::iter(chunk), true)
+            .await?
+        {
+            Ok(result) => result,
+            Err(e) => return Ok(Err(e)),
+        };
+
+        if last {
+            return Ok(Ok(result));
         }
-    }).await
+
+        options = options.expected_revision(ExpectedRevision::Exact(result.next_expected_version));
+    }
 }
 
 pub async fn batch_append<'a>(
     connection: &GrpcClient,
     options: &BatchAppendOptions,
 ) -> crate::Result<BatchAppendClient> {
+    let connection = connection.clone();
+    let (batch_sender, batch_receiver) = futures::channel::mpsc::unbounded();
+    let credentials = options
+        .credentials
+        .as_ref()
+        .cloned()
+        .or_else(|| connection.default_credentials());
+
+    let chunk_size = options.chunk_size;
+    let session_sender = batch_sender.clone();
+    let connector: crate::batch::Connector = Box::new(move |session| {
+        batch_append_session(
+            connection.clone(),
+            credentials.clone(),
+            chunk_size,
+            session,
+            session_sender.clone(),
+        )
+    });
+
+    Ok(BatchAppendClient::new(
+        batch_sender,
+        batch_receiver,
+        connector,
+        options.reconnect,
+    ))
+}
+
+/// Opens a batch-append session in the background. Failures are reported to the
+/// batch-append client through `BatchMsg::Error`.
+fn batch_append_session(
+    connection: GrpcClient,
+    credentials: Option<Credentials>,
+    chunk_size: Option<usize>,
+    session: u64,
+    batch_sender: futures::channel::mpsc::UnboundedSender<crate::batch::BatchMsg>,
+) -> futures::channel::mpsc::UnboundedSender<crate::batch::Req> {
     use futures::SinkExt;
     use streams::{
         batch_append_req::{options::ExpectedStreamPosition, Options, ProposedMessage},
@@ -549,21 +793,11 @@ pub async fn batch_append<'a>(
         BatchAppendReq,
     };
 
-    let connection = connection.clone();
     let (forward, receiver) = futures::channel::mpsc::unbounded::<crate::batch::Req>();
-    let (batch_sender, batch_receiver) = futures::channel::mpsc::unbounded();
     let mut cloned_batch_sender = batch_sender.clone();
+    let mut early_error_reporting = batch_sender;
 
-    let mut early_error_reporting = batch_sender.clone();
-    let batch_client = BatchAppendClient::new(batch_sender, batch_receiver, forward);
-
-    let credentials = options
-        .credentials
-        .as_ref()
-        .cloned()
-        .or_else(|| connection.default_credentials());
-
-    let receiver = receiver.map(|req| {
+    let receiver = receiver.flat_map(move |req| {
         let correlation_id = shared::uuid::Value::String(req.id.to_string());
         let correlation_id = Some(Uuid {
             value: Some(correlation_id),
@@ -581,24 +815,35 @@ pub async fn batch_append<'a>(
 
         let expected_stream_position = Some(expected_stream_position);
 
-        let proposed_messages: Vec<ProposedMessage> = req
-            .events
-            .into_iter()
-            .map(convert_event_data_to_batch_proposed_message)
-            .collect();
-
-        let options = Some(Options {
+        let mut options = Some(Options {
             stream_identifier,
             deadline: None,
             expected_stream_position,
         });
 
-        BatchAppendReq {
-            correlation_id,
-            options,
-            proposed_messages,
-            is_final: true,
-        }
+        // Every message of a request shares the same correlation id. Only the first one carries
+        // the options and only the last one is flagged as final.
+        let chunks = crate::batch::chunk_events(req.events, chunk_size);
+        let last = chunks.len() - 1;
+        let messages = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(idx, chunk)| {
+                let proposed_messages: Vec<ProposedMessage> = chunk
+                    .into_iter()
+                    .map(convert_event_data_to_batch_proposed_message)
+                    .collect();
+
+                BatchAppendReq {
+                    correlation_id: correlation_id.clone(),
+                    options: options.take(),
+                    proposed_messages,
+                    is_final: idx == last,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        stream::iter(messages)
     });
 
     tokio::spawn(async move {
@@ -618,7 +863,10 @@ pub async fn batch_append<'a>(
             Ok(value) => value,
             Err(e) => {
                 let _ = early_error_reporting
-                    .send(crate::batch::BatchMsg::Error(e.clone()))
+                    .send(crate::batch::BatchMsg::Error {
+                        session,
+                        error: e.clone(),
+                    })
                     .await;
 
                 return Err(e);
@@ -661,20 +909,15 @@ pub async fn batch_append<'a>(
                         }
                     });
 
-                    Ok(crate::batch::BatchWriteResult::new(
+                    Ok(Ok(crate::batch::BatchWriteResult::new(
                         stream_name,
                         current_revision,
                         position,
                         expected_version,
-                    ))
+                    )))
                 }
-                batch_append_resp::Result::Error(code) => {
-                    let message = code.message;
-                    let code = tonic::Code::from(code.code);
-                    let status = tonic::Status::new(code, message);
-                    let err = crate::Error::Grpc(status.to_string());
-
-                    Err(err)
+                batch_append_resp::Result::Error(status) => {
+                    Ok(Err(convert_batch_append_error(stream_name, status)))
                 }
             };
 
@@ -698,7 +941,10 @@ pub async fn batch_append<'a>(
 
                         // We notify the batch-append client that its session has been closed because of a gRPC error.
                         let _ = cloned_batch_sender
-                            .send(crate::batch::BatchMsg::Error(err))
+                            .send(crate::batch::BatchMsg::Error {
+                                session,
+                                error: err,
+                            })
                             .await;
                         break;
                     }
@@ -716,6 +962,17 @@ pub async fn batch_append<'a>(
                             continue;
                         }
 
+                        // The server closed the session, we notify the batch-append client so
+                        // it can open a new one.
+                        let _ = cloned_batch_sender
+                            .send(crate::batch::BatchMsg::Error {
+                                session,
+                                error: crate::Error::ServerError {
+                                    code: None,
+                                    message: "Batch-append session ended by the server".to_string(),
+                                },
+                            })
+                            .await;
                         break;
                     }
                 }
@@ -725,7 +982,7 @@ pub async fn batch_append<'a>(
         Ok::<(), crate::Error>(())
     });
 
-    Ok(batch_client)
+    forward
 }
 
 /// Sends asynchronously the read command to the server.
@@ -739,6 +996,7 @@ pub async fn read_stream<'a, S: AsRef<str>>(
     use streams::read_req::options::{self, StreamOption, StreamOptions};
     use streams::read_req::Options;
 
+    let resolve_link_tos = options.resolve_link_tos;
     let read_direction = match options.direction {
         ReadDirection::Forward => 0,
         ReadDirection::Backward => 1,
@@ -766,6 +1024,7 @@ pub async fn read_stream<'a, S: AsRef<str>>(
         .credentials
         .clone()
         .or_else(|| connection.default_credentials());
+    let retry = options.retry;
 
     let options = Options {
         stream_option: Some(StreamOption::Stream(stream_options)),
@@ -781,83 +1040,85 @@ pub async fn read_stream<'a, S: AsRef<str>>(
         options: Some(options),
     };
 
-    let mut req = Request::new(req);
+    connection
+        .execute_with_retry(retry.as_ref(), |channel| {
+            let mut req = Request::new(req.clone());
 
-    configure_auth_req(&mut req, credentials);
+            configure_auth_req(&mut req, credentials.clone());
 
-    connection
-        .execute(|channel| async {
-            let mut client = StreamsClient::new(channel.channel.clone());
-            let result = client.read(req).await;
+            async move {
+                let mut client = StreamsClient::new(channel.channel.clone());
+                let result = client.read(req).await;
 
-            if let Err(status) = result.as_ref() {
-                if let Some("stream-deleted") = status.metadata().get("exception").and_then(|e| e.to_str().ok()) {
-                    if let Some(stream_name) = status.metadata().get("stream-name").and_then(|e| e.to_str().ok()) {
-                        return Ok(ReadResult::StreamDeleted(stream_name.to_string()));
-                    }
+                if let Err(status) = result.as_ref() {
+                    if let Some("stream-deleted") = status.metadata().get("exception").and_then(|e| e.to_str().ok()) {
+                        if let Some(stream_name) = status.metadata().get("stream-name").and_then(|e| e.to_str().ok()) {
+                            return Ok(ReadResult::StreamDeleted(stream_name.to_string()));
+                        }
 
-                    warn!("stream-deleted exception didn't have a stream-name property, falling back to returning a generic gRPC error");
+                        warn!("stream-deleted exception didn't have a stream-name property, falling back to returning a generic gRPC error");
+                    }
                 }
-            }
 
-            let mut stream = result?.into_inner();
-
-            if let Some(resp) = stream.try_next().await? {
-                match resp.content.as_ref().unwrap() {
-                    streams::read_resp::Content::StreamNotFound(params) => {
-                        let stream_name = std::string::String::from_utf8(
-                            params
-                                .stream_identifier
-                                .as_ref()
-                                .unwrap()
-                                .stream_name
-                                .clone(),
-                        )
-                        .expect("Don't worry this string is valid!");
+                let mut stream = result?.into_inner();
+
+                if let Some(resp) = stream.try_next().await? {
+                    match resp.content.as_ref().unwrap() {
+                        streams::read_resp::Content::StreamNotFound(params) => {
+                            let stream_name = std::string::String::from_utf8(
+                                params
+                                    .stream_identifier
+                                    .as_ref()
+                                    .unwrap()
+                                    .stream_name
+                                    .clone(),
+                            )
+                            .expect("Don't worry this string is valid!");
+
+                            return Ok(ReadResult::StreamNotFound(stream_name));
+                        }
 
-                        return Ok(ReadResult::StreamNotFound(stream_name));
-                    }
+                        _ => {
+                            let stream = stream! {
+                                // We send back to the user the first event we received.
+                                if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
+                                    yield Ok(convert_proto_read_event(event, resolve_link_tos));
+                                }
 
-                    _ => {
-                        let stream = stream! {
-                            // We send back to the user the first event we received.
-                            if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
-                                yield Ok(convert_proto_read_event(event));
-                            }
+                                loop {
+                                    match stream.try_next().await {
+                                        Err(e) => {
+                                            let e = crate::Error::from_grpc(e);
 
-                            loop {
-                                match stream.try_next().await {
-                                    Err(e) => {
-                                        let e = crate::Error::from_grpc(e);
+                                            channel.report_error(e.clone()).await;
+                                            yield Err(e);
+                                            break;
+                                        }
 
-                                        channel.report_error(e.clone()).await;
-                                        yield Err(e);
-                                        break;
-                                    }
+                                        Ok(resp) => {
+                                            if let Some(resp) = resp {
+                                                if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
+                                                    yield Ok(convert_proto_read_event(event, resolve_link_tos));
+                                                }
 
-                                    Ok(resp) => {
-                                        if let Some(resp) = resp {
-                                            if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
-                                                yield Ok(convert_proto_read_event(event));
+                                                continue;
                                             }
 
-                                            continue;
+                                            break;
                                         }
-
-                                        break;
                                     }
                                 }
-                            }
-                        };
+                            };
 
-                        let stream: BoxStream<crate::Result<ResolvedEvent>> = Box::pin(stream);
+                            let stream: BoxStream<crate::Result<ResolvedEvent>> = Box::pin(stream);
 
-                        return Ok(ReadResult::Ok(stream));
+                            return Ok(ReadResult::Ok(stream));
+                        }
                     }
                 }
-            }
 
-            Ok(ReadResult::Ok(Box::pin(stream::empty())))
+                Ok(ReadResult::Ok(Box::pin(stream::empty())))
+            }
         })
         .await
 }
@@ -871,6 +1132,7 @@ pub async fn read_all<'a>(
     use streams::read_req::options::{self, AllOptions, StreamOption};
     use streams::read_req::Options;
 
+    let resolve_link_tos = options.resolve_link_tos;
     let read_direction = match options.direction {
         ReadDirection::Forward => 0,
         ReadDirection::Backward => 1,
@@ -902,11 +1164,15 @@ pub async fn read_all<'a>(
         .credentials
         .clone()
         .or_else(|| connection.default_credentials());
+    let retry = options.retry;
 
     let options = Options {
         stream_option: Some(StreamOption::All(stream_options)),
         resolve_links: options.resolve_link_tos,
-        filter_option: Some(options::FilterOption::NoFilter(Empty {})),
+        filter_option: Some(match options.filter.clone() {
+            Some(filter) => options::FilterOption::Filter(filter_into_proto(filter)),
+            None => options::FilterOption::NoFilter(Empty {}),
+        }),
         count_option: Some(options::CountOption::Count(count)),
         uuid_option: Some(uuid_option),
         control_option: None,
@@ -917,44 +1183,46 @@ pub async fn read_all<'a>(
         options: Some(options),
     };
 
-    let mut req = Request::new(req);
+    connection
+        .execute_with_retry(retry.as_ref(), |channel| {
+            let mut req = Request::new(req.clone());
 
-    configure_auth_req(&mut req, credentials);
+            configure_auth_req(&mut req, credentials.clone());
 
-    connection
-        .execute(|channel| async {
-            let mut client = StreamsClient::new(channel.channel.clone());
-            let mut stream = client.read(req).await?.into_inner();
+            async move {
+                let mut client = StreamsClient::new(channel.channel.clone());
+                let mut stream = client.read(req).await?.into_inner();
 
-            let stream = stream! {
-                loop {
-                    match stream.try_next().await {
-                        Err(e) => {
-                            let e = crate::Error::from_grpc(e);
+                let stream = stream! {
+                    loop {
+                        match stream.try_next().await {
+                            Err(e) => {
+                                let e = crate::Error::from_grpc(e);
 
-                            channel.report_error(e.clone()).await;
-                            yield Err(e);
-                            break;
-                        }
+                                channel.report_error(e.clone()).await;
+                                yield Err(e);
+                                break;
+                            }
 
-                        Ok(resp) => {
-                            if let Some(resp) = resp {
-                                if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
-                                    yield Ok(convert_proto_read_event(event));
+                            Ok(resp) => {
+                                if let Some(resp) = resp {
+                                    if let streams::read_resp::Content::Event(event) = resp.content.expect("content is defined") {
+                                        yield Ok(convert_proto_read_event(event, resolve_link_tos));
+                                    }
+
+                                    continue;
                                 }
 
-                                continue;
+                                break;
                             }
-
-                            break;
                         }
                     }
-                }
-            };
+                };
 
-            let stream: BoxStream<crate::Result<ResolvedEvent>> = Box::pin(stream);
+                let stream: BoxStream<crate::Result<ResolvedEvent>> = Box::pin(stream);
 
-            Ok(stream)
+                Ok(stream)
+            }
         })
         .await
 }
@@ -1093,6 +1361,7 @@ pub async fn subscribe_to_stream<'a, S: AsRef<str>>(
     use streams::read_req::options::{self, StreamOption, StreamOptions, SubscriptionOptions};
     use streams::read_req::Options;
 
+    let resolve_link_tos = options.resolve_link_tos;
     let read_direction = 0; // <- Going forward.
 
     let revision = match options.position {
@@ -1156,13 +1425,21 @@ pub async fn subscribe_to_stream<'a, S: AsRef<str>>(
                             if let Some(resp) = resp {
                                 match resp.content.expect("content is defined") {
                                     streams::read_resp::Content::Event(event) => {
-                                        yield Ok(SubEvent::EventAppeared(convert_proto_read_event(event)));
+                                        yield Ok(SubEvent::EventAppeared(convert_proto_read_event(event, resolve_link_tos)));
                                     }
 
                                     streams::read_resp::Content::Confirmation(sub) => {
                                         yield Ok(SubEvent::Confirmed(sub.subscription_id));
                                     }
 
+                                    streams::read_resp::Content::CaughtUp(_) => {
+                                        yield Ok(SubEvent::CaughtUp);
+                                    }
+
+                                    streams::read_resp::Content::FellBehind(_) => {
+                                        yield Ok(SubEvent::FellBehind);
+                                    }
+
                                     _ => {}
                                 }
                                 continue;
@@ -1189,6 +1466,7 @@ pub async fn subscribe_to_all<'a>(
     use streams::read_req::options::{self, AllOptions, StreamOption, SubscriptionOptions};
     use streams::read_req::Options;
 
+    let resolve_link_tos = options.resolve_link_tos;
     let read_direction = 0; // <- Going forward.
 
     let revision = match options.position {
@@ -1256,13 +1534,21 @@ pub async fn subscribe_to_all<'a>(
                             if let Some(resp) = resp {
                                 match resp.content.expect("content is defined") {
                                     streams::read_resp::Content::Event(event) => {
-                                        yield Ok(SubEvent::EventAppeared(convert_proto_read_event(event)));
+                                        yield Ok(SubEvent::EventAppeared(convert_proto_read_event(event, resolve_link_tos)));
                                     }
 
                                     streams::read_resp::Content::Confirmation(sub) => {
                                         yield Ok(SubEvent::Confirmed(sub.subscription_id));
                                     }
 
+                                    streams::read_resp::Content::CaughtUp(_) => {
+                                        yield Ok(SubEvent::CaughtUp);
+                                    }
+
+                                    streams::read_resp::Content::FellBehind(_) => {
+                                        yield Ok(SubEvent::FellBehind);
+                                    }
+
                                     streams::read_resp::Content::Checkpoint(chk) => {
                                         let position = Position {
                                             commit: chk.commit_position,
@@ -1290,6 +1576,121 @@ pub async fn subscribe_to_all<'a>(
         .await
 }
 
+/// Where a catch-up subscription becomes live, looked up alongside the subscription. Used when
+/// the server doesn't send `CaughtUp` notifications on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LiveHead {
+    /// The head couldn't be read in time, only the server notifications are relied on.
+    Unknown,
+    /// The subscription starts at the head already.
+    Live,
+    /// Last revision of the stream.
+    Revision(u64),
+    /// Last position of `$all`.
+    Position(Position),
+}
+
+impl LiveHead {
+    fn reached_by(&self, event: &SubEvent<ResolvedEvent>) -> bool {
+        match (self, event) {
+            (LiveHead::Live, _) => true,
+            (LiveHead::Revision(head), SubEvent::EventAppeared(event)) => {
+                event.get_original_event().revision >= *head
+            }
+            (LiveHead::Position(head), SubEvent::EventAppeared(event)) => {
+                event.get_original_event().position.commit >= head.commit
+            }
+            (LiveHead::Position(head), SubEvent::Checkpoint(position)) => {
+                position.commit >= head.commit
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Emits `CaughtUp` and `FellBehind` on phase changes only. Events are forwarded while the head
+/// lookup is pending, the last one being checked against the head once it's known. Notifications
+/// sent by the server take precedence: once one is received, the lookup is dropped and the head
+/// is no longer used.
+pub(crate) fn track_live_phase<'a>(
+    mut sub: BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>,
+    lookup: BoxFuture<'a, LiveHead>,
+) -> BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>> {
+    let stream = stream! {
+        let mut live = false;
+        let mut lookup = Some(lookup);
+        let mut head = LiveHead::Unknown;
+        // Last event received while the lookup was pending.
+        let mut last: Option<SubEvent<ResolvedEvent>> = None;
+
+        loop {
+            let item = match lookup.as_mut() {
+                Some(pending) => match futures::future::select(pending, sub.next()).await {
+                    Either::Right((item, _)) => item,
+                    Either::Left((resolved, _)) => {
+                        lookup = None;
+                        head = resolved;
+
+                        if !live && last.take().is_some_and(|event| head.reached_by(&event)) {
+                            live = true;
+                            yield Ok(SubEvent::CaughtUp);
+                        }
+
+                        continue;
+                    }
+                },
+                None => sub.next().await,
+            };
+
+            let item = match item {
+                Some(item) => item,
+                None => break,
+            };
+
+            match item {
+                Ok(SubEvent::CaughtUp) => {
+                    lookup = None;
+                    head = LiveHead::Unknown;
+
+                    if !live {
+                        live = true;
+                        yield Ok(SubEvent::CaughtUp);
+                    }
+                }
+
+                Ok(SubEvent::FellBehind) => {
+                    lookup = None;
+                    head = LiveHead::Unknown;
+
+                    if live {
+                        live = false;
+                        yield Ok(SubEvent::FellBehind);
+                    }
+                }
+
+                Ok(event) => {
+                    let reached = !live && head.reached_by(&event);
+
+                    if lookup.is_some() {
+                        last = Some(event.clone());
+                    }
+
+                    yield Ok(event);
+
+                    if reached {
+                        live = true;
+                        yield Ok(SubEvent::CaughtUp);
+                    }
+                }
+
+                Err(e) => yield Err(e),
+            }
+        }
+    };
+
+    Box::pin(stream)
+}
+
 /// This trait is used to avoid code duplication when introducing persistent subscription to $all. It
 /// allows us to re-use most of regular persistent subscription code.
 pub(crate) trait PsSettings {
@@ -1796,3 +2197,294 @@ impl SubscriptionWrite {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod commands_tests {
+    use super::{convert_batch_append_error, convert_proto_read_event, track_live_phase, LiveHead};
+    use crate::batch::BatchAppendError;
+    use crate::event_store::client::{shared, streams};
+    use crate::{
+        CurrentRevision, ExpectedRevision, Position, RecordedEvent, ResolvedEvent, SubEvent,
+        WrongExpectedVersion,
+    };
+    use futures::stream::BoxStream;
+    use futures::TryStreamExt;
+    use prost::Message;
+
+    fn status<M: Message>(type_name: &str, message: M) -> crate::google::rpc::Status {
+        crate::google::rpc::Status {
+            code: tonic::Code::FailedPrecondition as i32,
+            message: "boom".to_string(),
+            details: Some(prost_types::Any {
+                type_url: format!("type.googleapis.com/event_store.client.{}", type_name),
+                value: message.encode_to_vec(),
+            }),
+        }
+    }
+
+    #[test]
+    fn decode_batch_append_wrong_expected_version() {
+        use shared::wrong_expected_version::{
+            CurrentStreamRevisionOption, ExpectedStreamPositionOption,
+        };
+
+        let wev = shared::WrongExpectedVersion {
+            current_stream_revision_option: Some(
+                CurrentStreamRevisionOption::CurrentStreamRevision(3),
+            ),
+            expected_stream_position_option: Some(
+                ExpectedStreamPositionOption::ExpectedStreamPosition(1),
+            ),
+        };
+
+        let error =
+            convert_batch_append_error("foo".to_string(), status("WrongExpectedVersion", wev));
+
+        assert_eq!(
+            error,
+            BatchAppendError::WrongExpectedVersion(WrongExpectedVersion {
+                current: CurrentRevision::Current(3),
+                expected: ExpectedRevision::Exact(1),
+            })
+        );
+    }
+
+    #[test]
+    fn decode_batch_append_typed_errors() {
+        let deleted = shared::StreamDeleted {
+            stream_identifier: Some(shared::StreamIdentifier {
+                stream_name: b"bar".to_vec(),
+            }),
+        };
+
+        assert_eq!(
+            convert_batch_append_error("foo".to_string(), status("StreamDeleted", deleted)),
+            BatchAppendError::StreamDeleted {
+                stream_name: "bar".to_string()
+            }
+        );
+
+        assert_eq!(
+            convert_batch_append_error(
+                "foo".to_string(),
+                status(
+                    "MaximumAppendSizeExceeded",
+                    shared::MaximumAppendSizeExceeded {
+                        max_append_size: 1024
+                    }
+                )
+            ),
+            BatchAppendError::MaximumAppendSizeExceeded {
+                max_append_size: 1024
+            }
+        );
+
+        assert_eq!(
+            convert_batch_append_error(
+                "foo".to_string(),
+                status("AccessDenied", shared::AccessDenied {})
+            ),
+            BatchAppendError::AccessDenied
+        );
+
+        assert!(matches!(
+            convert_batch_append_error(
+                "foo".to_string(),
+                status("SomethingElse", shared::Empty {})
+            ),
+            BatchAppendError::Other {
+                code: tonic::Code::FailedPrecondition,
+                ..
+            }
+        ));
+    }
+
+    fn recorded_link(target: &str) -> streams::read_resp::read_event::RecordedEvent {
+        let id = shared::uuid::Value::String(uuid::Uuid::new_v4().to_string());
+
+        streams::read_resp::read_event::RecordedEvent {
+            id: Some(shared::Uuid { value: Some(id) }),
+            stream_identifier: Some(shared::StreamIdentifier {
+                stream_name: b"$ce-orders".to_vec(),
+            }),
+            stream_revision: 3,
+            prepare_position: 0,
+            commit_position: 0,
+            metadata: [("type".to_string(), "$>".to_string())].into(),
+            custom_metadata: Vec::new(),
+            data: target.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn report_unresolved_links_as_broken() {
+        let bare_link = || streams::read_resp::ReadEvent {
+            event: Some(recorded_link("0@orders-42")),
+            link: None,
+            position: None,
+        };
+
+        let resolved = convert_proto_read_event(bare_link(), true);
+
+        assert!(resolved.is_broken_link());
+        assert_eq!(
+            resolved.get_original_event().link_target(),
+            Some(("orders-42", 0))
+        );
+
+        // Without link resolution, links are regular events.
+        let resolved = convert_proto_read_event(bare_link(), false);
+
+        assert!(!resolved.is_broken_link());
+        assert!(resolved.event.is_some_and(|e| e.is_link()));
+    }
+
+    fn appeared(revision: u64) -> crate::Result<SubEvent<ResolvedEvent>> {
+        Ok(SubEvent::EventAppeared(ResolvedEvent {
+            event: Some(RecordedEvent {
+                stream_id: "foo".to_string(),
+                id: uuid::Uuid::new_v4(),
+                revision,
+                event_type: "foo-event".to_string(),
+                data: Default::default(),
+                metadata: Default::default(),
+                custom_metadata: Default::default(),
+                is_json: true,
+                position: Position {
+                    commit: revision * 100,
+                    prepare: revision * 100,
+                },
+            }),
+            link: None,
+            commit_position: None,
+        }))
+    }
+
+    async fn phases(
+        items: Vec<crate::Result<SubEvent<ResolvedEvent>>>,
+        head: LiveHead,
+    ) -> crate::Result<Vec<String>> {
+        let sub = track_live_phase(
+            Box::pin(futures::stream::iter(items)),
+            Box::pin(futures::future::ready(head)),
+        );
+        let events = sub.try_collect::<Vec<_>>().await?;
+
+        Ok(events.iter().map(phase).collect())
+    }
+
+    fn phase(event: &SubEvent<ResolvedEvent>) -> String {
+        match event {
+            SubEvent::Confirmed(_) => "confirmed".to_string(),
+            SubEvent::EventAppeared(e) => e.get_original_event().revision.to_string(),
+            SubEvent::Checkpoint(_) => "checkpoint".to_string(),
+            SubEvent::CaughtUp => "caught-up".to_string(),
+            SubEvent::FellBehind => "fell-behind".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_live_phase_changes() -> crate::Result<()> {
+        let confirmed = || Ok(SubEvent::Confirmed("sub".to_string()));
+
+        // Old servers: the head read at subscribe time tells when history is processed.
+        let events = phases(
+            vec![confirmed(), appeared(0), appeared(1), appeared(2)],
+            LiveHead::Revision(1),
+        )
+        .await?;
+
+        assert_eq!(events, vec!["confirmed", "0", "1", "caught-up", "2"]);
+
+        let events = phases(
+            vec![confirmed(), appeared(3)],
+            LiveHead::Position(Position {
+                commit: 300,
+                prepare: 300,
+            }),
+        )
+        .await?;
+
+        assert_eq!(events, vec!["confirmed", "3", "caught-up"]);
+
+        let events = phases(vec![confirmed(), appeared(0)], LiveHead::Live).await?;
+
+        assert_eq!(events, vec!["confirmed", "caught-up", "0"]);
+
+        // Server notifications are forwarded, without duplicating the one already inferred.
+        let events = phases(
+            vec![
+                confirmed(),
+                appeared(0),
+                Ok(SubEvent::CaughtUp),
+                Ok(SubEvent::FellBehind),
+                appeared(1),
+                Ok(SubEvent::CaughtUp),
+            ],
+            LiveHead::Revision(0),
+        )
+        .await?;
+
+        assert_eq!(
+            events,
+            vec![
+                "confirmed",
+                "0",
+                "caught-up",
+                "fell-behind",
+                "1",
+                "caught-up"
+            ]
+        );
+
+        let events = phases(vec![confirmed(), appeared(5)], LiveHead::Unknown).await?;
+
+        assert_eq!(events, vec!["confirmed", "5"]);
+
+        Ok(())
+    }
+
+    async fn next(
+        sub: &mut BoxStream<'_, crate::Result<SubEvent<ResolvedEvent>>>,
+    ) -> crate::Result<Option<String>> {
+        Ok(sub.try_next().await?.as_ref().map(phase))
+    }
+
+    #[tokio::test]
+    async fn look_up_head_alongside_subscription() -> crate::Result<()> {
+        let (events, items) = futures::channel::mpsc::unbounded();
+        let (head, lookup) = futures::channel::oneshot::channel();
+        let lookup = async move { lookup.await.unwrap_or(LiveHead::Unknown) };
+        let mut sub = track_live_phase(Box::pin(items), Box::pin(lookup));
+
+        // Events aren't held back by the lookup, the last one is checked once it completes.
+        events.unbounded_send(appeared(0)).unwrap();
+        events.unbounded_send(appeared(1)).unwrap();
+
+        assert_eq!(next(&mut sub).await?.as_deref(), Some("0"));
+        assert_eq!(next(&mut sub).await?.as_deref(), Some("1"));
+
+        head.send(LiveHead::Revision(1)).unwrap();
+        events.unbounded_send(appeared(2)).unwrap();
+        drop(events);
+
+        assert_eq!(next(&mut sub).await?.as_deref(), Some("caught-up"));
+        assert_eq!(next(&mut sub).await?.as_deref(), Some("2"));
+        assert_eq!(next(&mut sub).await?, None);
+
+        // Once the server sends notifications, the lookup is no longer waited for.
+        let items = vec![appeared(0), Ok(SubEvent::CaughtUp), appeared(1)];
+        let sub = track_live_phase(
+            Box::pin(futures::stream::iter(items)),
+            Box::pin(futures::future::pending()),
+        );
+        let events = sub.try_collect::<Vec<_>>().await?;
+
+        assert_eq!(
+            events.iter().map(phase).collect::<Vec<_>>(),
+            vec!["0", "caught-up", "1"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,703 @@
+//! Client-side payload encryption, used to implement crypto-shredding.
+//!
+//! Event payloads and custom metadata are encrypted with a per-stream (or per-subject) key before
+//! being sent to the server. Keys are provided by a [`KeyStore`]. Because events are immutable,
+//! deleting the key of a subject is the way to make its personal data unreadable: reading such
+//! events returns [`Decrypted::Shredded`] instead of failing.
+//!
+//! ```no_run
+//! # use eventstore::{Client, EventData, Encryptor, InMemoryKeyStore};
+//! # async fn doc(client: Client) -> eventstore::Result<()> {
+//! let encryptor = Encryptor::new(InMemoryKeyStore::new());
+//! let event = EventData::binary("user-registered", "john.doe@example.com".into());
+//!
+//! encryptor
+//!     .append_to_stream(&client, "user-42", &Default::default(), vec![event])
+//!     .await?;
+//!
+//! // Forgets everything about that user.
+//! encryptor.key_store().delete_key("user-42").await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{BufMut, Bytes, BytesMut};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use rand::RngCore;
+
+use crate::batch::{BatchAppendClient, BatchAppendError, BatchWriteResult};
+use crate::codec::OCTET_STREAM_CONTENT_TYPE;
+use crate::{
+    All, AppendToStreamOptions, Client, EventData, ExpectedRevision, ReadResult, ReadStreamOptions,
+    RecordedEvent, ResolvedEvent, SubEvent, SubscribeToAllOptions, SubscribeToStreamOptions,
+    WriteResult, WrongExpectedVersion,
+};
+
+const MAGIC: &[u8; 4] = b"ESE1";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// A 256-bit symmetric key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Generates a new random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+
+        EncryptionKey(bytes)
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        EncryptionKey(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptionKey(<redacted>)")
+    }
+}
+
+/// Stores the encryption keys. Implementations are expected to persist keys in a place
+/// distinct from EventStoreDB, such as a KMS or a vault.
+#[async_trait]
+pub trait KeyStore: Send + Sync {
+    /// Returns the key associated to the given id, if it still exists.
+    async fn get_key(&self, key_id: &str) -> crate::Result<Option<EncryptionKey>>;
+
+    /// Returns the key associated to the given id, creating it if needed.
+    async fn get_or_create_key(&self, key_id: &str) -> crate::Result<EncryptionKey>;
+
+    /// Deletes a key. Every event encrypted with that key becomes unreadable.
+    async fn delete_key(&self, key_id: &str) -> crate::Result<()>;
+}
+
+/// A [`KeyStore`] keeping its keys in memory. Mostly useful for testing.
+#[derive(Default, Clone)]
+pub struct InMemoryKeyStore {
+    keys: Arc<Mutex<HashMap<String, EncryptionKey>>>,
+}
+
+impl InMemoryKeyStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[async_trait]
+impl KeyStore for InMemoryKeyStore {
+    async fn get_key(&self, key_id: &str) -> crate::Result<Option<EncryptionKey>> {
+        Ok(self.keys.lock().unwrap().get(key_id).cloned())
+    }
+
+    async fn get_or_create_key(&self, key_id: &str) -> crate::Result<EncryptionKey> {
+        let mut keys = self.keys.lock().unwrap();
+        let key = keys
+            .entry(key_id.to_string())
+            .or_insert_with(EncryptionKey::generate);
+
+        Ok(key.clone())
+    }
+
+    async fn delete_key(&self, key_id: &str) -> crate::Result<()> {
+        self.keys.lock().unwrap().remove(key_id);
+        Ok(())
+    }
+}
+
+/// Result of decrypting an event.
+#[derive(Debug, Clone)]
+pub enum Decrypted {
+    /// The event was decrypted, or wasn't encrypted in the first place.
+    Readable(ResolvedEvent),
+
+    /// The key used to encrypt the event has been deleted, or the event can't be authenticated
+    /// with the key now stored under that id: the key was recreated after being deleted, or the
+    /// event was tampered with. The event is returned as stored on the server.
+    Shredded {
+        key_id: String,
+        event: ResolvedEvent,
+    },
+}
+
+impl Decrypted {
+    pub fn is_shredded(&self) -> bool {
+        matches!(self, Decrypted::Shredded { .. })
+    }
+
+    /// Returns the decrypted event, if its key still exists.
+    pub fn readable(self) -> Option<ResolvedEvent> {
+        match self {
+            Decrypted::Readable(event) => Some(event),
+            Decrypted::Shredded { .. } => None,
+        }
+    }
+}
+
+type KeyIdResolver = dyn Fn(&str, &EventData) -> String + Send + Sync;
+
+/// Encrypts events before they are appended and decrypts them when they are read.
+///
+/// By default, events are encrypted with a key per stream. Use [`Encryptor::key_id_with`] to use
+/// a key per subject instead.
+#[derive(Clone)]
+pub struct Encryptor {
+    key_store: Arc<dyn KeyStore>,
+    key_id: Arc<KeyIdResolver>,
+}
+
+impl Encryptor {
+    pub fn new<K>(key_store: K) -> Self
+    where
+        K: KeyStore + 'static,
+    {
+        Self {
+            key_store: Arc::new(key_store),
+            key_id: Arc::new(|stream_name, _| stream_name.to_string()),
+        }
+    }
+
+    /// Selects the key id used to encrypt an event, given the stream it's appended to.
+    pub fn key_id_with<F>(self, resolver: F) -> Self
+    where
+        F: Fn(&str, &EventData) -> String + Send + Sync + 'static,
+    {
+        Self {
+            key_id: Arc::new(resolver),
+            ..self
+        }
+    }
+
+    pub fn key_store(&self) -> &dyn KeyStore {
+        self.key_store.as_ref()
+    }
+
+    /// Encrypts the payload and custom metadata of the given events.
+    pub async fn encrypt(
+        &self,
+        stream_name: &str,
+        events: Vec<EventData>,
+    ) -> crate::Result<Vec<EventData>> {
+        let mut keys = HashMap::<String, EncryptionKey>::new();
+        let mut encrypted = Vec::with_capacity(events.len());
+
+        for mut event in events {
+            let key_id = (self.key_id)(stream_name, &event);
+            let key = match keys.get(&key_id) {
+                Some(key) => key.clone(),
+                None => {
+                    let key = self.key_store.get_or_create_key(&key_id).await?;
+                    keys.insert(key_id.clone(), key.clone());
+                    key
+                }
+            };
+
+            let content_type = event
+                .metadata
+                .get("content-type")
+                .cloned()
+                .unwrap_or_default();
+
+            event.payload = seal(&key, &key_id, content_type.as_str(), &event.payload)?;
+            event.custom_metadata = match event.custom_metadata.take() {
+                Some(custom_metadata) => Some(seal(&key, &key_id, "", &custom_metadata)?),
+                None => None,
+            };
+
+            event.metadata.insert(
+                "content-type".to_string(),
+                OCTET_STREAM_CONTENT_TYPE.to_string(),
+            );
+
+            encrypted.push(event);
+        }
+
+        Ok(encrypted)
+    }
+
+    /// Decrypts an event. Events that were not encrypted are returned as-is.
+    pub async fn decrypt(&self, event: ResolvedEvent) -> crate::Result<Decrypted> {
+        let ResolvedEvent {
+            event: recorded,
+            link,
+            commit_position,
+        } = event;
+
+        let recorded = match recorded {
+            None => None,
+            Some(recorded) => match self.decrypt_recorded(recorded).await? {
+                Ok(recorded) => Some(recorded),
+                Err((key_id, recorded)) => {
+                    return Ok(Decrypted::Shredded {
+                        key_id,
+                        event: ResolvedEvent {
+                            event: Some(recorded),
+                            link,
+                            commit_position,
+                        },
+                    })
+                }
+            },
+        };
+
+        Ok(Decrypted::Readable(ResolvedEvent {
+            event: recorded,
+            link,
+            commit_position,
+        }))
+    }
+
+    // Payloads starting with the envelope magic, but without a complete envelope header, are plain
+    // payloads and are returned as-is. Envelopes that can't be opened with their key are shredded.
+    async fn decrypt_recorded(
+        &self,
+        mut event: RecordedEvent,
+    ) -> crate::Result<std::result::Result<RecordedEvent, (String, RecordedEvent)>> {
+        let envelope = match Envelope::parse(&event.data) {
+            Some(envelope) => envelope,
+            None => return Ok(Ok(event)),
+        };
+
+        let key_id = envelope.key_id.to_string();
+        let key = match self.key_store.get_key(&key_id).await? {
+            Some(key) => key,
+            None => return Ok(Err((key_id, event))),
+        };
+
+        let (content_type, data) = match envelope.open(&key) {
+            Some(opened) => opened,
+            None => return Ok(Err((key_id, event))),
+        };
+
+        if let Some(envelope) = Envelope::parse(&event.custom_metadata) {
+            match envelope.open(&key) {
+                Some((_, custom_metadata)) => event.custom_metadata = custom_metadata,
+                None => return Ok(Err((key_id, event))),
+            }
+        }
+
+        event.is_json = content_type == crate::codec::JSON_CONTENT_TYPE;
+        event
+            .metadata
+            .insert("content-type".to_string(), content_type);
+        event.data = data;
+
+        Ok(Ok(event))
+    }
+
+    /// Decrypts every event of a read stream.
+    pub fn decrypt_stream<'a>(
+        &self,
+        stream: BoxStream<'a, crate::Result<ResolvedEvent>>,
+    ) -> BoxStream<'a, crate::Result<Decrypted>> {
+        let this = self.clone();
+
+        stream
+            .and_then(move |event| {
+                let this = this.clone();
+                async move { this.decrypt(event).await }
+            })
+            .boxed()
+    }
+
+    /// Decrypts every event of a catch-up subscription.
+    pub fn decrypt_subscription<'a>(
+        &self,
+        stream: BoxStream<'a, crate::Result<SubEvent<ResolvedEvent>>>,
+    ) -> BoxStream<'a, crate::Result<SubEvent<Decrypted>>> {
+        let this = self.clone();
+
+        stream
+            .and_then(move |event| {
+                let this = this.clone();
+                async move {
+                    let event = match event {
+                        SubEvent::EventAppeared(event) => {
+                            SubEvent::EventAppeared(this.decrypt(event).await?)
+                        }
+                        SubEvent::Confirmed(id) => SubEvent::Confirmed(id),
+                        SubEvent::Checkpoint(position) => SubEvent::Checkpoint(position),
+                        SubEvent::CaughtUp => SubEvent::CaughtUp,
+                        SubEvent::FellBehind => SubEvent::FellBehind,
+                    };
+
+                    Ok(event)
+                }
+            })
+            .boxed()
+    }
+
+    /// Encrypts then sends events to a given stream.
+    pub async fn append_to_stream(
+        &self,
+        client: &Client,
+        stream_name: impl AsRef<str>,
+        options: &AppendToStreamOptions,
+        events: Vec<EventData>,
+    ) -> crate::Result<Result<WriteResult, WrongExpectedVersion>> {
+        let events = self.encrypt(stream_name.as_ref(), events).await?;
+
+        client.append_to_stream(stream_name, options, events).await
+    }
+
+    /// Encrypts then sends events to a given stream through a batch-append session.
+    pub async fn batch_append_to_stream(
+        &self,
+        client: &BatchAppendClient,
+        stream_name: impl AsRef<str>,
+        expected_revision: ExpectedRevision,
+        events: Vec<EventData>,
+    ) -> crate::Result<Result<BatchWriteResult, BatchAppendError>> {
+        let events = self.encrypt(stream_name.as_ref(), events).await?;
+
+        client
+            .append_to_stream(stream_name, expected_revision, events)
+            .await
+    }
+
+    /// Reads and decrypts every event of a given stream.
+    pub async fn read_stream(
+        &self,
+        client: &Client,
+        stream_name: impl AsRef<str>,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<ReadResult<BoxStream<'static, crate::Result<Decrypted>>>> {
+        let result = client.read_stream(stream_name, options, All).await?;
+
+        Ok(match result {
+            ReadResult::Ok(stream) => ReadResult::Ok(self.decrypt_stream(stream)),
+            ReadResult::StreamNotFound(stream_name) => ReadResult::StreamNotFound(stream_name),
+            ReadResult::StreamDeleted(stream_name) => ReadResult::StreamDeleted(stream_name),
+        })
+    }
+
+    /// Subscribes to a given stream, decrypting events as they come.
+    pub async fn subscribe_to_stream<'a>(
+        &self,
+        client: &Client,
+        stream_name: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<Decrypted>>>> {
+        let stream = client.subscribe_to_stream(stream_name, options).await?;
+
+        Ok(self.decrypt_subscription(stream))
+    }
+
+    /// Subscribes to `$all`, decrypting events as they come.
+    pub async fn subscribe_to_all<'a>(
+        &self,
+        client: &Client,
+        options: &SubscribeToAllOptions,
+    ) -> crate::Result<BoxStream<'a, crate::Result<SubEvent<Decrypted>>>> {
+        let stream = client.subscribe_to_all(options).await?;
+
+        Ok(self.decrypt_subscription(stream))
+    }
+}
+
+// Envelope layout:
+//   magic (4 bytes) | key id length (u16) | key id | nonce (12 bytes) | ciphertext
+//
+// Plaintext layout:
+//   content-type length (u16) | content-type | payload
+fn seal(
+    key: &EncryptionKey,
+    key_id: &str,
+    content_type: &str,
+    payload: &[u8],
+) -> crate::Result<Bytes> {
+    let content_type_len = length_prefix("content-type", content_type)?;
+    let key_id_len = length_prefix("key id", key_id)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut plaintext = BytesMut::with_capacity(2 + content_type.len() + payload.len());
+    plaintext.put_u16(content_type_len);
+    plaintext.put_slice(content_type.as_bytes());
+    plaintext.put_slice(payload);
+
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .map_err(|e| crate::Error::EncryptionError(e.to_string()))?;
+
+    let mut envelope =
+        BytesMut::with_capacity(MAGIC.len() + 2 + key_id.len() + NONCE_LEN + ciphertext.len());
+    envelope.put_slice(MAGIC);
+    envelope.put_u16(key_id_len);
+    envelope.put_slice(key_id.as_bytes());
+    envelope.put_slice(&nonce);
+    envelope.put_slice(&ciphertext);
+
+    Ok(envelope.freeze())
+}
+
+fn length_prefix(name: &str, value: &str) -> crate::Result<u16> {
+    u16::try_from(value.len()).map_err(|_| {
+        crate::Error::EncryptionError(format!(
+            "{} is {} bytes long, at most {} bytes are supported",
+            name,
+            value.len(),
+            u16::MAX
+        ))
+    })
+}
+
+struct Envelope<'a> {
+    key_id: &'a str,
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Envelope<'a> {
+    // Returns `None` when the bytes don't hold a complete envelope header followed by at least an
+    // authentication tag and a content-type length.
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return None;
+        }
+
+        let key_id_len = BigEndian::read_u16(&bytes[MAGIC.len()..]) as usize;
+        let key_id_start = MAGIC.len() + 2;
+        let nonce_start = key_id_start + key_id_len;
+        let key_id = std::str::from_utf8(bytes.get(key_id_start..nonce_start)?).ok()?;
+        let nonce = bytes.get(nonce_start..nonce_start + NONCE_LEN)?;
+        let ciphertext = &bytes[nonce_start + NONCE_LEN..];
+
+        if ciphertext.len() < TAG_LEN + 2 {
+            return None;
+        }
+
+        Some(Envelope {
+            key_id,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    // Returns `None` when the envelope can't be authenticated with the given key.
+    fn open(&self, key: &EncryptionKey) -> Option<(String, Bytes)> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(self.nonce),
+                Payload {
+                    msg: self.ciphertext,
+                    aad: self.key_id.as_bytes(),
+                },
+            )
+            .ok()?;
+
+        if plaintext.len() < 2 {
+            return None;
+        }
+
+        let content_type_len = BigEndian::read_u16(&plaintext) as usize;
+        let content_type = plaintext.get(2..2 + content_type_len)?;
+        let content_type = String::from_utf8(content_type.to_vec()).ok()?;
+        let payload = Bytes::copy_from_slice(&plaintext[2 + content_type_len..]);
+
+        Some((content_type, payload))
+    }
+}
+
+#[cfg(test)]
+mod encryption_tests {
+    use super::{Decrypted, Encryptor, InMemoryKeyStore, KeyStore};
+    use crate::{EventData, Position, RecordedEvent, ResolvedEvent};
+
+    fn resolved(event: EventData) -> ResolvedEvent {
+        let recorded = RecordedEvent {
+            stream_id: "user-42".to_string(),
+            id: uuid::Uuid::new_v4(),
+            revision: 0,
+            event_type: event.metadata.get("type").cloned().unwrap(),
+            is_json: false,
+            data: event.payload,
+            metadata: event.metadata,
+            custom_metadata: event.custom_metadata.unwrap_or_default(),
+            position: Position::start(),
+        };
+
+        ResolvedEvent {
+            event: Some(recorded),
+            link: None,
+            commit_position: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypt_round_trip() -> crate::Result<()> {
+        let encryptor = Encryptor::new(InMemoryKeyStore::new());
+        let event = EventData::json(
+            "user-registered",
+            serde_json::json!({ "email": "foo@bar.com" }),
+        )
+        .unwrap()
+        .metadata("secret".into());
+
+        let encrypted = encryptor.encrypt("user-42", vec![event.clone()]).await?;
+        let encrypted = encrypted.into_iter().next().unwrap();
+
+        assert_ne!(encrypted.payload, event.payload);
+        assert_ne!(encrypted.custom_metadata, event.custom_metadata);
+
+        let decrypted = encryptor
+            .decrypt(resolved(encrypted))
+            .await?
+            .readable()
+            .expect("key to exist");
+        let decrypted = decrypted.get_original_event();
+
+        assert_eq!(decrypted.data, event.payload);
+        assert_eq!(
+            Some(decrypted.custom_metadata.clone()),
+            event.custom_metadata
+        );
+        assert_eq!(decrypted.content_type(), Some("application/json"));
+        assert!(decrypted.is_json);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deleted_key_shreds_events() -> crate::Result<()> {
+        let key_store = InMemoryKeyStore::new();
+        let encryptor = Encryptor::new(key_store.clone()).key_id_with(|_, _| "subject".to_string());
+        let event = EventData::binary("user-registered", "foo@bar.com".into());
+        let encrypted = encryptor.encrypt("user-42", vec![event]).await?;
+
+        key_store.delete_key("subject").await?;
+
+        let decrypted = encryptor
+            .decrypt(resolved(encrypted.into_iter().next().unwrap()))
+            .await?;
+
+        assert!(matches!(decrypted, Decrypted::Shredded { ref key_id, .. } if key_id == "subject"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plain_events_are_left_untouched() -> crate::Result<()> {
+        let encryptor = Encryptor::new(InMemoryKeyStore::new());
+        let event = EventData::binary("foo-event", "plain".into());
+        let decrypted = encryptor
+            .decrypt(resolved(event.clone()))
+            .await?
+            .readable()
+            .unwrap();
+
+        assert_eq!(decrypted.get_original_event().data, event.payload);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plain_payloads_starting_with_magic_are_left_untouched() -> crate::Result<()> {
+        let key_store = InMemoryKeyStore::new();
+        key_store.get_or_create_key("ab").await?;
+
+        let encryptor = Encryptor::new(key_store);
+
+        for payload in [
+            &b"ESE1"[..],
+            b"ESE1\x00\x02ab not an envelope",
+            b"ESE1\xff\xff",
+        ] {
+            let event = EventData::binary("foo-event", bytes::Bytes::from_static(payload));
+            let decrypted = encryptor
+                .decrypt(resolved(event.clone()))
+                .await?
+                .readable()
+                .unwrap();
+
+            assert_eq!(decrypted.get_original_event().data, event.payload);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_envelopes_shred_events() -> crate::Result<()> {
+        let key_store = InMemoryKeyStore::new();
+        let encryptor = Encryptor::new(key_store.clone()).key_id_with(|_, _| "subject".to_string());
+        let event =
+            EventData::binary("user-registered", "foo@bar.com".into()).metadata("secret".into());
+        let encrypted = encryptor
+            .encrypt("user-42", vec![event])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap();
+
+        let mut tampered_payload = encrypted.clone();
+        let mut payload = tampered_payload.payload.to_vec();
+        *payload.last_mut().unwrap() ^= 1;
+        tampered_payload.payload = payload.into();
+
+        let mut tampered_metadata = encrypted.clone();
+        let mut custom_metadata = tampered_metadata.custom_metadata.unwrap().to_vec();
+        *custom_metadata.last_mut().unwrap() ^= 1;
+        tampered_metadata.custom_metadata = Some(custom_metadata.into());
+
+        for event in [tampered_payload, tampered_metadata] {
+            match encryptor.decrypt(resolved(event.clone())).await? {
+                Decrypted::Shredded {
+                    key_id,
+                    event: stored,
+                } => {
+                    assert_eq!(key_id, "subject");
+                    assert_eq!(stored.get_original_event().data, event.payload);
+                }
+                Decrypted::Readable(_) => panic!("tampered event decrypted"),
+            }
+        }
+
+        // A key recreated under the same id doesn't open the events sealed with the deleted one.
+        key_store.delete_key("subject").await?;
+        key_store.get_or_create_key("subject").await?;
+
+        match encryptor.decrypt(resolved(encrypted.clone())).await? {
+            Decrypted::Shredded { key_id, event } => {
+                assert_eq!(key_id, "subject");
+                assert_eq!(event.get_original_event().data, encrypted.payload);
+            }
+            Decrypted::Readable(_) => panic!("event decrypted with the wrong key"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_oversized_key_id() {
+        let encryptor =
+            Encryptor::new(InMemoryKeyStore::new()).key_id_with(|_, _| "k".repeat(70_000));
+        let event = EventData::binary("foo-event", "plain".into());
+
+        assert!(matches!(
+            encryptor.encrypt("user-42", vec![event]).await,
+            Err(crate::Error::EncryptionError(_))
+        ));
+    }
+}
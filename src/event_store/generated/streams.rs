@@ -155,7 +155,7 @@ pub mod read_req {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReadResp {
-    #[prost(oneof = "read_resp::Content", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "read_resp::Content", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
     pub content: ::core::option::Option<read_resp::Content>,
 }
 /// Nested message and enum types in `ReadResp`.
@@ -218,6 +218,10 @@ pub mod read_resp {
         #[prost(message, optional, tag = "1")]
         pub stream_identifier: ::core::option::Option<super::super::StreamIdentifier>,
     }
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CaughtUp {}
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct FellBehind {}
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Content {
         #[prost(message, tag = "1")]
@@ -234,6 +238,10 @@ pub mod read_resp {
         LastStreamPosition(u64),
         #[prost(message, tag = "7")]
         LastAllStreamPosition(super::super::AllStreamPosition),
+        #[prost(message, tag = "8")]
+        CaughtUp(CaughtUp),
+        #[prost(message, tag = "9")]
+        FellBehind(FellBehind),
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -0,0 +1,755 @@
+//! Dumps the content of a database, or of a selection of streams, to a file and replays it.
+//!
+//! Two file formats are supported: newline-delimited JSON, one [`ExportedEvent`] per line, and
+//! a compact binary format made of length-prefixed records. Importing detects the format on its
+//! own.
+//!
+//! ```no_run
+//! # use eventstore::{Client, ExportFormat, ExportOptions, Exporter, SubscriptionFilter};
+//! # async fn doc(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = Exporter::new(client);
+//! let options = ExportOptions::default()
+//!     .format(ExportFormat::Binary)
+//!     .filter(SubscriptionFilter::on_stream_name().add_prefix("order-"));
+//!
+//! exporter.export_all("orders.bin", &options).await?;
+//! exporter.import("orders.bin", &Default::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::path::Path;
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter, Lines};
+
+use crate::{
+    All, AppendToStreamOptions, ArchivedEvent, BatchAppendClient, BatchAppendOptions, Client,
+    Credentials, EventData, ExpectedRevision, Position, ReadAllOptions, ReadResult,
+    ReadStreamOptions, RecordedEvent, SubscriptionFilter,
+};
+
+const BINARY_MAGIC: &[u8; 4] = b"ESX1";
+
+// Largest binary record accepted when importing, well above the server maximum event size.
+const MAX_BINARY_RECORD_LEN: usize = 64 * 1_024 * 1_024;
+
+/// An event as stored in an export file, along with its position in the transaction file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedEvent {
+    #[serde(flatten)]
+    pub event: ArchivedEvent,
+    pub commit_position: u64,
+    pub prepare_position: u64,
+}
+
+impl ExportedEvent {
+    pub fn position(&self) -> Position {
+        Position {
+            commit: self.commit_position,
+            prepare: self.prepare_position,
+        }
+    }
+}
+
+impl From<RecordedEvent> for ExportedEvent {
+    fn from(event: RecordedEvent) -> Self {
+        let position = event.position;
+
+        ExportedEvent {
+            event: ArchivedEvent::from(event),
+            commit_position: position.commit,
+            prepare_position: position.prepare,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Export I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed export file: {0}")]
+    Format(String),
+    #[error("Append to stream {stream_name} failed: {reason}")]
+    Append { stream_name: String, reason: String },
+    #[error("Import interrupted after {processed} events: {error}")]
+    Interrupted {
+        /// How many records of the file were imported, or skipped, before the failure. Passing
+        /// it to `ImportOptions::skip` resumes the import where it stopped.
+        processed: usize,
+        #[source]
+        error: Box<ExportError>,
+    },
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(e: serde_json::Error) -> Self {
+        ExportError::Format(e.to_string())
+    }
+}
+
+/// Layout of an export file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Newline-delimited JSON, payloads being base64-encoded.
+    #[default]
+    Ndjson,
+    /// Length-prefixed binary records, smaller and faster to process.
+    Binary,
+}
+
+#[derive(Clone, Default)]
+/// Options of the export commands.
+pub struct ExportOptions {
+    pub(crate) format: ExportFormat,
+    pub(crate) filter: Option<SubscriptionFilter>,
+    pub(crate) include_system_streams: bool,
+    pub(crate) credentials: Option<Credentials>,
+}
+
+impl ExportOptions {
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Layout of the export file. Default: `ExportFormat::Ndjson`.
+    pub fn format(self, format: ExportFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Only exports the events of `$all` matching the filter. The filter is applied
+    /// server-side.
+    pub fn filter(self, filter: SubscriptionFilter) -> Self {
+        Self {
+            filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// Also exports the events of system streams, those starting with `$`. They are skipped by
+    /// default, as most of them can't be appended to.
+    pub fn include_system_streams(self) -> Self {
+        Self {
+            include_system_streams: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Options of the import command.
+pub struct ImportOptions {
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) skip: usize,
+    pub(crate) max_batch: usize,
+    pub(crate) batch_append: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            credentials: None,
+            skip: 0,
+            max_batch: 500,
+            batch_append: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Skips the given number of records at the beginning of the file, to resume an interrupted
+    /// import. See `ExportError::Interrupted`.
+    pub fn skip(self, skip: usize) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Maximum number of events sent in a single append. Consecutive events of the same stream
+    /// are appended together. Default: 500.
+    pub fn max_batch(self, max_batch: usize) -> Self {
+        Self {
+            max_batch: max_batch.max(1),
+            ..self
+        }
+    }
+
+    /// Appends the events through a batch-append session instead of regular appends.
+    pub fn batch_append(self) -> Self {
+        Self {
+            batch_append: true,
+            ..self
+        }
+    }
+}
+
+/// Outcome of an export.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportResult {
+    /// How many events were written to the file.
+    pub exported: usize,
+    /// Position of the last exported event, if any event was exported.
+    pub last_position: Option<Position>,
+}
+
+/// Outcome of an import.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportResult {
+    /// How many events were appended.
+    pub imported: usize,
+    /// How many records were skipped at the beginning of the file.
+    pub skipped: usize,
+}
+
+/// Exports events to files and imports them back.
+#[derive(Clone)]
+pub struct Exporter {
+    client: Client,
+}
+
+impl Exporter {
+    pub fn new(client: Client) -> Self {
+        Exporter { client }
+    }
+
+    /// Exports the events of `$all`, replacing the file if it exists.
+    pub async fn export_all(
+        &self,
+        path: impl AsRef<Path>,
+        options: &ExportOptions,
+    ) -> Result<ExportResult, ExportError> {
+        let mut read_options = ReadAllOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        if let Some(filter) = options.filter.clone() {
+            read_options = read_options.filter(filter);
+        }
+
+        let mut writer = RecordWriter::create(path.as_ref(), options.format).await?;
+        let mut events = self.client.read_all(&read_options, All).await?;
+
+        while let Some(event) = events.try_next().await? {
+            if let Some(event) = event.event {
+                if options.include_system_streams || !event.stream_id.starts_with('$') {
+                    writer.write(&ExportedEvent::from(event)).await?;
+                }
+            }
+        }
+
+        writer.finish().await
+    }
+
+    /// Exports the events of the given streams, one stream after the other. Streams that don't
+    /// exist or were deleted are skipped.
+    pub async fn export_streams<I, S>(
+        &self,
+        streams: I,
+        path: impl AsRef<Path>,
+        options: &ExportOptions,
+    ) -> Result<ExportResult, ExportError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut read_options = ReadStreamOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            read_options = read_options.authenticated(credentials);
+        }
+
+        let mut writer = RecordWriter::create(path.as_ref(), options.format).await?;
+
+        for stream_name in streams {
+            let stream_name = stream_name.as_ref();
+            let mut events = match self
+                .client
+                .read_stream(stream_name, &read_options, All)
+                .await?
+            {
+                ReadResult::Ok(events) => events,
+                _ => {
+                    warn!("Stream {} not found or deleted, skipping it", stream_name);
+                    continue;
+                }
+            };
+
+            while let Some(event) = events.try_next().await? {
+                if let Some(event) = event.event {
+                    writer.write(&ExportedEvent::from(event)).await?;
+                }
+            }
+        }
+
+        writer.finish().await
+    }
+
+    /// Appends the events of an export file to their original streams, in order, keeping their
+    /// ids. Events are appended without expected revision, in which case the server only
+    /// deduplicates events by id on a best-effort basis: importing the same file twice may
+    /// duplicate events. Use `ImportOptions::skip` to resume an interrupted import instead.
+    pub async fn import(
+        &self,
+        path: impl AsRef<Path>,
+        options: &ImportOptions,
+    ) -> Result<ImportResult, ExportError> {
+        let mut reader = RecordReader::open(path.as_ref()).await?;
+        let sink = ImportSink::new(&self.client, options).await?;
+        let mut processed = 0usize;
+        let mut pending = Vec::new();
+        let mut pending_stream = String::new();
+
+        let interrupted = |processed: usize, error: ExportError| ExportError::Interrupted {
+            processed,
+            error: Box::new(error),
+        };
+
+        loop {
+            let record = match reader.next().await {
+                Ok(record) => record,
+                Err(e) => return Err(interrupted(processed, e)),
+            };
+
+            let flush = match record.as_ref() {
+                Some(record) => {
+                    record.event.stream_id != pending_stream || pending.len() >= options.max_batch
+                }
+                None => true,
+            };
+
+            if flush && !pending.is_empty() {
+                let count = pending.len();
+
+                if let Err(e) = sink
+                    .append(pending_stream.as_str(), std::mem::take(&mut pending))
+                    .await
+                {
+                    return Err(interrupted(processed, e));
+                }
+
+                processed += count;
+            }
+
+            let record = match record {
+                Some(record) => record,
+                None => break,
+            };
+
+            if processed < options.skip {
+                processed += 1;
+                continue;
+            }
+
+            pending_stream = record.event.stream_id.clone();
+            pending.push(EventData::from(record.event));
+        }
+
+        Ok(ImportResult {
+            imported: processed - options.skip.min(processed),
+            skipped: options.skip.min(processed),
+        })
+    }
+}
+
+enum ImportSink<'a> {
+    Append(&'a Client, AppendToStreamOptions),
+    Batch(BatchAppendClient),
+}
+
+impl<'a> ImportSink<'a> {
+    async fn new(
+        client: &'a Client,
+        options: &ImportOptions,
+    ) -> Result<ImportSink<'a>, ExportError> {
+        if options.batch_append {
+            let mut batch_options = BatchAppendOptions::default();
+
+            if let Some(credentials) = options.credentials.clone() {
+                batch_options = batch_options.authenticated(credentials);
+            }
+
+            return Ok(ImportSink::Batch(
+                client.batch_append(&batch_options).await?,
+            ));
+        }
+
+        let mut append_options = AppendToStreamOptions::default();
+
+        if let Some(credentials) = options.credentials.clone() {
+            append_options = append_options.authenticated(credentials);
+        }
+
+        Ok(ImportSink::Append(client, append_options))
+    }
+
+    async fn append(&self, stream_name: &str, events: Vec<EventData>) -> Result<(), ExportError> {
+        let failure = |reason: String| ExportError::Append {
+            stream_name: stream_name.to_string(),
+            reason,
+        };
+
+        match self {
+            ImportSink::Append(client, options) => {
+                client
+                    .append_to_stream(stream_name, options, events)
+                    .await?
+                    .map_err(|e| failure(e.to_string()))?;
+            }
+
+            ImportSink::Batch(batch) => {
+                batch
+                    .append_to_stream(stream_name, ExpectedRevision::Any, events)
+                    .await?
+                    .map_err(|e| failure(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct RecordWriter {
+    format: ExportFormat,
+    inner: BufWriter<tokio::fs::File>,
+    exported: usize,
+    last_position: Option<Position>,
+}
+
+impl RecordWriter {
+    async fn create(path: &Path, format: ExportFormat) -> Result<Self, ExportError> {
+        let mut inner = BufWriter::new(tokio::fs::File::create(path).await?);
+
+        if format == ExportFormat::Binary {
+            inner.write_all(BINARY_MAGIC).await?;
+        }
+
+        Ok(RecordWriter {
+            format,
+            inner,
+            exported: 0,
+            last_position: None,
+        })
+    }
+
+    async fn write(&mut self, record: &ExportedEvent) -> Result<(), ExportError> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                let mut line = serde_json::to_vec(record)?;
+                line.push(b'\n');
+                self.inner.write_all(&line).await?;
+            }
+
+            ExportFormat::Binary => {
+                let body = binary::encode_record(record)?;
+                self.inner.write_u32(body.len() as u32).await?;
+                self.inner.write_all(&body).await?;
+            }
+        }
+
+        self.exported += 1;
+        self.last_position = Some(record.position());
+
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<ExportResult, ExportError> {
+        self.inner.flush().await?;
+        self.inner.into_inner().sync_all().await?;
+
+        Ok(ExportResult {
+            exported: self.exported,
+            last_position: self.last_position,
+        })
+    }
+}
+
+enum RecordReader {
+    Ndjson(Lines<BufReader<tokio::fs::File>>),
+    Binary(BufReader<tokio::fs::File>),
+}
+
+impl RecordReader {
+    async fn open(path: &Path) -> Result<Self, ExportError> {
+        let mut reader = BufReader::new(tokio::fs::File::open(path).await?);
+
+        if reader.fill_buf().await?.starts_with(BINARY_MAGIC) {
+            reader.consume(BINARY_MAGIC.len());
+
+            return Ok(RecordReader::Binary(reader));
+        }
+
+        Ok(RecordReader::Ndjson(reader.lines()))
+    }
+
+    async fn next(&mut self) -> Result<Option<ExportedEvent>, ExportError> {
+        match self {
+            RecordReader::Ndjson(lines) => {
+                while let Some(line) = lines.next_line().await? {
+                    if !line.trim().is_empty() {
+                        return Ok(Some(serde_json::from_str(&line)?));
+                    }
+                }
+
+                Ok(None)
+            }
+
+            RecordReader::Binary(reader) => {
+                let mut prefix = [0u8; 4];
+                let mut read = 0;
+
+                while read < prefix.len() {
+                    match reader.read(&mut prefix[read..]).await? {
+                        0 if read == 0 => return Ok(None),
+                        0 => {
+                            return Err(ExportError::Format(
+                                "file ends within a record length".to_string(),
+                            ))
+                        }
+                        n => read += n,
+                    }
+                }
+
+                let len = u32::from_be_bytes(prefix) as usize;
+
+                if len > MAX_BINARY_RECORD_LEN {
+                    return Err(ExportError::Format(format!(
+                        "record of {} bytes exceeds the maximum of {} bytes",
+                        len, MAX_BINARY_RECORD_LEN
+                    )));
+                }
+
+                let mut body = vec![0; len];
+
+                if let Err(e) = reader.read_exact(&mut body).await {
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                        return Err(ExportError::Format("file ends within a record".to_string()));
+                    }
+
+                    return Err(e.into());
+                }
+
+                binary::decode_record(&body).map(Some)
+            }
+        }
+    }
+}
+
+/// Compact binary layout of a record: id, revision and positions, then length-prefixed strings
+/// and payloads, all integers being big-endian.
+mod binary {
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+    use bytes::Bytes;
+    use uuid::Uuid;
+
+    use super::{ExportError, ExportedEvent};
+    use crate::ArchivedEvent;
+
+    fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) -> std::io::Result<()> {
+        buf.write_u32::<BigEndian>(bytes.len() as u32)?;
+        buf.extend_from_slice(bytes);
+
+        Ok(())
+    }
+
+    fn read_bytes(cursor: &mut std::io::Cursor<&[u8]>) -> Result<Vec<u8>, ExportError> {
+        let len = cursor.read_u32::<BigEndian>()? as usize;
+        let remaining = cursor.get_ref().len() - cursor.position() as usize;
+
+        if len > remaining {
+            return Err(ExportError::Format(format!(
+                "field of {} bytes overflows its record",
+                len
+            )));
+        }
+
+        let mut bytes = vec![0; len];
+        cursor.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    fn read_string(cursor: &mut std::io::Cursor<&[u8]>) -> Result<String, ExportError> {
+        String::from_utf8(read_bytes(cursor)?).map_err(|e| ExportError::Format(e.to_string()))
+    }
+
+    pub(super) fn encode_record(record: &ExportedEvent) -> std::io::Result<Vec<u8>> {
+        let event = &record.event;
+        let mut buf = Vec::with_capacity(64 + event.data.len() + event.custom_metadata.len());
+
+        buf.extend_from_slice(event.id.as_bytes());
+        buf.write_u64::<BigEndian>(event.revision)?;
+        buf.write_u64::<BigEndian>(record.commit_position)?;
+        buf.write_u64::<BigEndian>(record.prepare_position)?;
+        write_bytes(&mut buf, event.stream_id.as_bytes())?;
+        write_bytes(&mut buf, event.event_type.as_bytes())?;
+        buf.write_u32::<BigEndian>(event.metadata.len() as u32)?;
+
+        for (key, value) in event.metadata.iter() {
+            write_bytes(&mut buf, key.as_bytes())?;
+            write_bytes(&mut buf, value.as_bytes())?;
+        }
+
+        write_bytes(&mut buf, &event.custom_metadata)?;
+        write_bytes(&mut buf, &event.data)?;
+
+        Ok(buf)
+    }
+
+    pub(super) fn decode_record(body: &[u8]) -> Result<ExportedEvent, ExportError> {
+        let mut cursor = std::io::Cursor::new(body);
+        let mut id = [0u8; 16];
+
+        cursor.read_exact(&mut id)?;
+
+        let revision = cursor.read_u64::<BigEndian>()?;
+        let commit_position = cursor.read_u64::<BigEndian>()?;
+        let prepare_position = cursor.read_u64::<BigEndian>()?;
+        let stream_id = read_string(&mut cursor)?;
+        let event_type = read_string(&mut cursor)?;
+        let count = cursor.read_u32::<BigEndian>()?;
+        let mut metadata = HashMap::new();
+
+        for _ in 0..count {
+            let key = read_string(&mut cursor)?;
+            let value = read_string(&mut cursor)?;
+
+            metadata.insert(key, value);
+        }
+
+        let custom_metadata = Bytes::from(read_bytes(&mut cursor)?);
+        let data = Bytes::from(read_bytes(&mut cursor)?);
+
+        Ok(ExportedEvent {
+            event: ArchivedEvent {
+                id: Uuid::from_bytes(id),
+                stream_id,
+                revision,
+                event_type,
+                metadata,
+                custom_metadata,
+                data,
+            },
+            commit_position,
+            prepare_position,
+        })
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::binary::{decode_record, encode_record};
+    use super::{
+        ExportError, ExportFormat, ExportedEvent, RecordReader, RecordWriter, BINARY_MAGIC,
+    };
+    use crate::ArchivedEvent;
+    use bytes::Bytes;
+
+    fn exported(revision: u64) -> ExportedEvent {
+        ExportedEvent {
+            event: ArchivedEvent {
+                id: uuid::Uuid::new_v4(),
+                stream_id: "orders-42".to_string(),
+                revision,
+                event_type: "order-placed".to_string(),
+                metadata: [
+                    ("type".to_string(), "order-placed".to_string()),
+                    ("content-type".to_string(), "application/json".to_string()),
+                ]
+                .into(),
+                custom_metadata: Bytes::from_static(b"{\"user\":\"bob\"}"),
+                data: Bytes::from_static(b"{\"total\":42}"),
+            },
+            commit_position: 1_000 + revision,
+            prepare_position: 1_000 + revision,
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_both_formats() -> Result<(), Box<dyn std::error::Error>> {
+        let records = vec![exported(0), exported(1), exported(2)];
+
+        for format in [ExportFormat::Ndjson, ExportFormat::Binary] {
+            let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+            let mut writer = RecordWriter::create(&path, format).await?;
+
+            for record in records.iter() {
+                writer.write(record).await?;
+            }
+
+            let result = writer.finish().await?;
+
+            assert_eq!(result.exported, 3);
+            assert_eq!(result.last_position, Some(records[2].position()));
+
+            let mut reader = RecordReader::open(&path).await?;
+            let mut actual = Vec::new();
+
+            while let Some(record) = reader.next().await? {
+                actual.push(record);
+            }
+
+            let _ = tokio::fs::remove_file(&path).await;
+
+            assert_eq!(actual, records, "{:?} round trip", format);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_truncated_and_oversized_binary_files() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let body = encode_record(&exported(0))?;
+        let mut record = (body.len() as u32).to_be_bytes().to_vec();
+        record.extend_from_slice(&body);
+
+        for tail in [
+            &record[..2],
+            &record[..record.len() - 1],
+            &u32::MAX.to_be_bytes()[..],
+        ] {
+            let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+            let mut content = BINARY_MAGIC.to_vec();
+            content.extend_from_slice(&record);
+            content.extend_from_slice(tail);
+            tokio::fs::write(&path, content).await?;
+
+            let mut reader = RecordReader::open(&path).await?;
+            let first = reader.next().await;
+            let second = reader.next().await;
+
+            let _ = tokio::fs::remove_file(&path).await;
+
+            assert!(matches!(first, Ok(Some(_))));
+            assert!(matches!(second, Err(ExportError::Format(_))));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_truncated_binary_records() {
+        let body = encode_record(&exported(0)).unwrap();
+
+        assert!(decode_record(&body).is_ok());
+        assert!(decode_record(&body[..body.len() - 1]).is_err());
+    }
+}
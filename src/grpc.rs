@@ -1,4 +1,5 @@
 use crate::gossip::{Gossip, MemberInfo, VNodeState};
+use crate::options::retry::RetryOptions;
 use crate::types::{Endpoint, GrpcConnectionError};
 use crate::{Credentials, DnsClusterSettings, Either, NodePreference};
 use futures::channel::mpsc::UnboundedSender;
@@ -925,6 +926,7 @@ impl std::fmt::Debug for Msg {
 pub struct GrpcClient {
     sender: futures::channel::mpsc::UnboundedSender<Msg>,
     default_credentials: Option<Credentials>,
+    default_retry: Option<RetryOptions>,
 }
 
 impl GrpcClient {
@@ -945,9 +947,20 @@ impl GrpcClient {
         GrpcClient {
             sender,
             default_credentials,
+            default_retry: None,
         }
     }
 
+    /// Sets the retry policy used by idempotent operations that don't specify their own.
+    pub(crate) fn set_default_retry(&mut self, retry: Option<RetryOptions>) {
+        self.default_retry = retry;
+    }
+
+    /// Returns the retry policy applying to an operation, favoring the per-call one.
+    pub(crate) fn retry_policy(&self, retry: Option<&RetryOptions>) -> Option<RetryOptions> {
+        retry.copied().or(self.default_retry)
+    }
+
     pub(crate) async fn execute<F, Fut, A>(&self, action: F) -> crate::Result<A>
     where
         F: FnOnce(Handle) -> Fut + Send,
@@ -974,6 +987,45 @@ impl GrpcClient {
         }
     }
 
+    /// Executes an idempotent action, retrying it on retryable errors according to the given
+    /// retry policy, or the client-wide one if none is provided. Without any retry policy, the
+    /// action is executed only once.
+    pub(crate) async fn execute_with_retry<F, Fut, A>(
+        &self,
+        retry: Option<&RetryOptions>,
+        mut action: F,
+    ) -> crate::Result<A>
+    where
+        F: FnMut(Handle) -> Fut + Send,
+        Fut: Future<Output = Result<A, Status>> + Send,
+        A: Send,
+    {
+        let retry = match self.retry_policy(retry) {
+            Some(retry) => retry,
+            None => return self.execute(action).await,
+        };
+
+        let mut attempt = 0usize;
+
+        loop {
+            match self.execute(&mut action).await {
+                Err(e) if e.is_retryable() && retry.can_retry(attempt) => {
+                    let delay = retry.delay_for(attempt);
+                    attempt += 1;
+
+                    warn!(
+                        "Retryable error, retrying operation ({}/{}) in {:?}: {}",
+                        attempt, retry.limit, delay, e
+                    );
+
+                    tokio::time::sleep(delay).await;
+                }
+
+                result => return result,
+            }
+        }
+    }
+
     pub(crate) async fn current_selected_node(&self) -> crate::Result<Handle> {
         let (sender, consumer) = futures::channel::oneshot::channel();
 
@@ -1000,8 +1052,8 @@ pub(crate) async fn handle_error<A>(
     connection_id: Uuid,
     err: crate::Error,
 ) -> crate::Result<A> {
-    if let crate::Error::ServerError(ref status) = err {
-        error!("Current selected EventStoreDB node gone unavailable. Starting node selection process: {}", status);
+    if let crate::Error::ServerError { ref message, .. } = err {
+        error!("Current selected EventStoreDB node gone unavailable. Starting node selection process: {}", message);
 
         let _ = sender
             .clone()
@@ -1017,8 +1069,8 @@ pub(crate) async fn handle_error<A>(
             "NotLeaderException found. Start reconnection process on: {:?}",
             leader
         );
-    } else if let crate::Error::Grpc(ref e) = err {
-        debug!("Operation unexpected error: {}", e);
+    } else if let crate::Error::Grpc { .. } = err {
+        debug!("Operation unexpected error: {}", err);
     }
 
     Err(err)
@@ -61,15 +61,24 @@
 #[macro_use]
 extern crate log;
 
+mod aggregate;
+mod archive;
 mod batch;
+mod checkpoint;
 mod client;
+mod codec;
 mod commands;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod event_store;
+mod export;
 mod gossip;
 mod grpc;
 mod options;
 mod private;
 mod projection_client;
+mod replication;
+mod stream_name;
 mod types;
 
 pub(crate) mod google {
@@ -78,34 +87,68 @@ pub(crate) mod google {
     }
 }
 
+pub use aggregate::*;
+pub use archive::*;
+pub use batch::{BatchAppendClient, BatchAppendError, BatchAppendState, BatchWriteResult};
+pub use checkpoint::*;
 pub use client::Client;
+pub use codec::*;
 pub use commands::{SubscriptionRead, SubscriptionWrite};
+#[cfg(feature = "encryption")]
+pub use encryption::*;
+pub use export::*;
 pub use grpc::{ClientSettings, ClientSettingsParseError};
 pub use options::append_to_stream::*;
+pub use options::batch_append::*;
 pub use options::delete_stream::*;
+pub use options::list_streams::*;
 pub use options::persistent_subscription::*;
+pub use options::projections::*;
 pub use options::read_all::*;
+pub use options::read_decide_append::*;
 pub use options::read_stream::*;
 pub use options::retry::*;
+pub use options::stream_state::*;
 pub use options::subscribe_to_all::*;
 pub use options::subscribe_to_stream::*;
 pub use options::tombstone_stream::*;
+pub use options::update_stream_metadata::*;
 pub use projection_client::*;
+pub use replication::*;
+pub use stream_name::*;
 pub use types::*;
 
 pub mod prelude {
+    pub use crate::aggregate::*;
+    pub use crate::archive::*;
+    pub use crate::batch::{
+        BatchAppendClient, BatchAppendError, BatchAppendState, BatchWriteResult,
+    };
+    pub use crate::checkpoint::*;
     pub use crate::client::Client;
+    pub use crate::codec::*;
     pub use crate::commands::{SubscriptionRead, SubscriptionWrite};
+    #[cfg(feature = "encryption")]
+    pub use crate::encryption::*;
+    pub use crate::export::*;
     pub use crate::grpc::{ClientSettings, ClientSettingsParseError};
     pub use crate::options::append_to_stream::*;
+    pub use crate::options::batch_append::*;
     pub use crate::options::delete_stream::*;
+    pub use crate::options::list_streams::*;
     pub use crate::options::persistent_subscription::*;
+    pub use crate::options::projections::*;
     pub use crate::options::read_all::*;
+    pub use crate::options::read_decide_append::*;
     pub use crate::options::read_stream::*;
     pub use crate::options::retry::*;
+    pub use crate::options::stream_state::*;
     pub use crate::options::subscribe_to_all::*;
     pub use crate::options::subscribe_to_stream::*;
     pub use crate::options::tombstone_stream::*;
+    pub use crate::options::update_stream_metadata::*;
     pub use crate::projection_client::*;
+    pub use crate::replication::*;
+    pub use crate::stream_name::*;
     pub use crate::types::*;
 }
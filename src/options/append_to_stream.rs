@@ -1,5 +1,6 @@
 use crate::event_store::client::shared::Empty;
 use crate::event_store::client::streams::append_req::options::ExpectedStreamRevision;
+use crate::options::retry::RetryOptions;
 use crate::private::Sealed;
 use crate::{Credentials, EventData, ExpectedRevision};
 use futures::future::Ready;
@@ -11,6 +12,8 @@ use futures::Stream;
 pub struct AppendToStreamOptions {
     pub(crate) version: ExpectedStreamRevision,
     pub(crate) credentials: Option<Credentials>,
+    pub(crate) chunk_size: Option<usize>,
+    pub(crate) retry: Option<RetryOptions>,
 }
 
 impl Default for AppendToStreamOptions {
@@ -18,6 +21,8 @@ impl Default for AppendToStreamOptions {
         Self {
             version: ExpectedStreamRevision::Any(Empty {}),
             credentials: None,
+            chunk_size: None,
+            retry: None,
         }
     }
 }
@@ -43,12 +48,61 @@ impl AppendToStreamOptions {
 
         Self { version, ..self }
     }
+
+    /// Splits the events into several appends, each of them staying under the given size in
+    /// bytes. Every append expects the revision the previous one ended at, and the `WriteResult`
+    /// of the last append is returned. Useful to stay under the server maximum append size when
+    /// appending a long stream of events.
+    ///
+    /// The appends are not atomic: if one of them fails, the events of the previous ones are
+    /// already written. An event bigger than the budget is sent on its own.
+    pub fn chunk_size(self, bytes: usize) -> Self {
+        Self {
+            chunk_size: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Retries the append when it fails with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy.
+    ///
+    /// An append is only retried when every event has an explicit id (see `EventData::id`), so
+    /// the server can deduplicate an append that went through before the error, and when its
+    /// events are known upfront, which excludes `Streaming` appends. Otherwise it is sent only
+    /// once.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
 }
 
-pub struct Streaming<S>(S);
+/// Appends events as they are produced by a [`Stream`]. Events aren't buffered, so such an append
+/// is never retried, whatever the retry policy.
+///
+/// ```
+/// # use eventstore::{EventData, Streaming};
+/// let events = futures::stream::iter(vec![EventData::binary("foo", "bar".into())]);
+/// let events = Streaming::new(events);
+/// ```
+pub struct Streaming<S>(pub S);
+
+impl<S> Streaming<S>
+where
+    S: Stream<Item = EventData> + Send + Sync,
+{
+    pub fn new(stream: S) -> Self {
+        Streaming(stream)
+    }
+}
 
 pub trait ToEvents: Sealed {
     type Events: Stream<Item = EventData> + Send + Sync;
+
+    /// Whether every event is known upfront, which the append needs to be retried.
+    const BUFFERED: bool = true;
+
     fn into_events(self) -> Self::Events;
 }
 
@@ -74,6 +128,8 @@ where
 {
     type Events = S;
 
+    const BUFFERED: bool = false;
+
     fn into_events(self) -> Self::Events {
         self.0
     }
@@ -1,8 +1,11 @@
+use crate::options::retry::RetryOptions;
 use crate::Credentials;
 
 #[derive(Clone, Default)]
 pub struct BatchAppendOptions {
     pub(crate) credentials: Option<Credentials>,
+    pub(crate) reconnect: RetryOptions,
+    pub(crate) chunk_size: Option<usize>,
 }
 
 impl BatchAppendOptions {
@@ -10,6 +13,24 @@ impl BatchAppendOptions {
     pub fn authenticated(self, credentials: Credentials) -> Self {
         Self {
             credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// When the batch-append session breaks, sets how many times and how fast we try to open a
+    /// new one before failing pending requests. Requests without a response are resent on the
+    /// new session. Default: 3 attempts, 500ms apart.
+    pub fn reconnect_options(self, reconnect: RetryOptions) -> Self {
+        Self { reconnect, ..self }
+    }
+
+    /// Splits the events of a request into several gRPC messages, each of them staying under
+    /// the given size in bytes. The server still processes the request as a single append and
+    /// a single `BatchWriteResult` is returned. By default, a request is sent as one message.
+    pub fn chunk_size(self, bytes: usize) -> Self {
+        Self {
+            chunk_size: Some(bytes),
+            ..self
         }
     }
 }
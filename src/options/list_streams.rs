@@ -0,0 +1,150 @@
+use crate::stream_name::{CategorySplit, DEFAULT_CATEGORY_SEPARATOR};
+use crate::Credentials;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum StreamNameFilter {
+    Prefix(String),
+    Category {
+        category: String,
+        separator: char,
+        split: CategorySplit,
+    },
+}
+
+impl StreamNameFilter {
+    pub(crate) fn matches(&self, stream_name: &str) -> bool {
+        match self {
+            StreamNameFilter::Prefix(prefix) => stream_name.starts_with(prefix.as_str()),
+            StreamNameFilter::Category {
+                category,
+                separator,
+                split,
+            } => split.category_of(stream_name, *separator) == Some(category.as_str()),
+        }
+    }
+
+    fn targets_system_streams(&self) -> bool {
+        match self {
+            StreamNameFilter::Prefix(value)
+            | StreamNameFilter::Category {
+                category: value, ..
+            } => value.starts_with('$'),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+/// Options of the list streams command.
+pub struct ListStreamsOptions {
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) filter: Option<StreamNameFilter>,
+    pub(crate) skip: usize,
+    pub(crate) max_count: Option<usize>,
+    pub(crate) scan_all_fallback: bool,
+}
+
+impl ListStreamsOptions {
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Only lists the streams whose name starts with the given prefix.
+    pub fn prefix(self, prefix: impl AsRef<str>) -> Self {
+        Self {
+            filter: Some(StreamNameFilter::Prefix(prefix.as_ref().to_string())),
+            ..self
+        }
+    }
+
+    /// Only lists the streams of the given category, the category of a stream being the part
+    /// of its name before the first `-`, like the `$by_category` system projection does by
+    /// default. Use [`category_with`] if that projection is configured differently.
+    ///
+    /// [`category_with`]: #method.category_with
+    pub fn category(self, category: impl AsRef<str>) -> Self {
+        self.category_with(category, DEFAULT_CATEGORY_SEPARATOR, CategorySplit::First)
+    }
+
+    /// Only lists the streams of the given category, splitting stream names at the first or last
+    /// occurrence of the given separator.
+    pub fn category_with(
+        self,
+        category: impl AsRef<str>,
+        separator: char,
+        split: CategorySplit,
+    ) -> Self {
+        Self {
+            filter: Some(StreamNameFilter::Category {
+                category: category.as_ref().to_string(),
+                separator,
+                split,
+            }),
+            ..self
+        }
+    }
+
+    /// Skips the given number of stream names, after filtering. Combined with `max_count`, it
+    /// allows to list the streams page by page. Skipped names are still read from the server.
+    pub fn skip(self, skip: usize) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Returns at most the given number of stream names.
+    pub fn max_count(self, max_count: usize) -> Self {
+        Self {
+            max_count: Some(max_count),
+            ..self
+        }
+    }
+
+    /// When the `$streams` system projection is disabled, lists the streams by scanning `$all`
+    /// instead of failing. Scanning reads every event of the database, so it gets slower as the
+    /// database grows.
+    pub fn scan_all_fallback(self) -> Self {
+        Self {
+            scan_all_fallback: true,
+            ..self
+        }
+    }
+
+    /// Tells whether a stream name should be listed. System streams are skipped, unless the
+    /// filter targets them explicitly.
+    pub(crate) fn accepts(&self, stream_name: &str) -> bool {
+        match self.filter.as_ref() {
+            Some(filter) if filter.targets_system_streams() => filter.matches(stream_name),
+            Some(filter) => !stream_name.starts_with('$') && filter.matches(stream_name),
+            None => !stream_name.starts_with('$'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod list_streams_tests {
+    use super::ListStreamsOptions;
+    use crate::stream_name::CategorySplit;
+
+    #[test]
+    fn filter_by_category() {
+        let options = ListStreamsOptions::default().category("shopping");
+
+        assert!(options.accepts("shopping-cart-1"));
+        assert!(!options.accepts("shoppingcart"));
+        assert!(!options.accepts("$ce-shopping"));
+
+        let options =
+            ListStreamsOptions::default().category_with("shopping-cart", '-', CategorySplit::Last);
+
+        assert!(options.accepts("shopping-cart-1"));
+        assert!(!options.accepts("shopping-1"));
+
+        let options =
+            ListStreamsOptions::default().category_with("order", '_', CategorySplit::First);
+
+        assert!(options.accepts("order_42"));
+        assert!(!options.accepts("order-42"));
+    }
+}
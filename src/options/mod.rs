@@ -1,11 +1,15 @@
 pub mod append_to_stream;
 pub mod batch_append;
 pub mod delete_stream;
+pub mod list_streams;
 pub mod persistent_subscription;
 pub mod projections;
 pub mod read_all;
+pub mod read_decide_append;
 pub mod read_stream;
 pub mod retry;
+pub mod stream_state;
 pub mod subscribe_to_all;
 pub mod subscribe_to_stream;
 pub mod tombstone_stream;
+pub mod update_stream_metadata;
@@ -1,3 +1,4 @@
+use crate::options::retry::RetryOptions;
 use crate::types::Credentials;
 
 #[derive(Debug, Clone, Default)]
@@ -103,6 +104,7 @@ impl DeleteProjectionOptions {
 pub struct GetStateProjectionOptions {
     pub(crate) credentials: Option<Credentials>,
     pub(crate) partition: String,
+    pub(crate) retry: Option<RetryOptions>,
 }
 
 impl GetStateProjectionOptions {
@@ -123,12 +125,22 @@ impl GetStateProjectionOptions {
             ..self
         }
     }
+
+    /// Retries the read when it fails with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct GetResultProjectionOptions {
     pub(crate) credentials: Option<Credentials>,
     pub(crate) partition: String,
+    pub(crate) retry: Option<RetryOptions>,
 }
 
 impl GetResultProjectionOptions {
@@ -149,4 +161,13 @@ impl GetResultProjectionOptions {
             ..self
         }
     }
+
+    /// Retries the read when it fails with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use crate::{Credentials, Position, ReadDirection, StreamPosition};
+use crate::options::retry::RetryOptions;
+use crate::{Credentials, Position, ReadDirection, StreamPosition, SubscriptionFilter};
 
 #[derive(Clone)]
 pub struct ReadAllOptions {
@@ -6,6 +7,8 @@ pub struct ReadAllOptions {
     pub(crate) direction: ReadDirection,
     pub(crate) position: StreamPosition<Position>,
     pub(crate) resolve_link_tos: bool,
+    pub(crate) retry: Option<RetryOptions>,
+    pub(crate) filter: Option<SubscriptionFilter>,
 }
 
 impl Default for ReadAllOptions {
@@ -15,6 +18,8 @@ impl Default for ReadAllOptions {
             direction: ReadDirection::Forward,
             position: StreamPosition::Start,
             resolve_link_tos: false,
+            retry: None,
+            filter: None,
         }
     }
 }
@@ -73,4 +78,23 @@ impl ReadAllOptions {
             ..self
         }
     }
+
+    /// Retries the read when it fails with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy. Only the call establishing the
+    /// read is retried, not the consumption of the returned stream.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
+
+    /// Filters the events server-side, only returning those whose stream name or event type
+    /// match the filter.
+    pub fn filter(self, filter: SubscriptionFilter) -> Self {
+        Self {
+            filter: Some(filter),
+            ..self
+        }
+    }
 }
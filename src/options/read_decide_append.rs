@@ -0,0 +1,37 @@
+use crate::options::retry::RetryOptions;
+use crate::Credentials;
+
+#[derive(Clone)]
+/// Options of the read-decide-append command.
+pub struct ReadDecideAppendOptions {
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) retry: RetryOptions,
+}
+
+impl Default for ReadDecideAppendOptions {
+    fn default() -> Self {
+        Self {
+            credentials: None,
+            retry: RetryOptions::default()
+                .retry_delay(std::time::Duration::from_millis(50))
+                .retry_backoff(2)
+                .retry_max_delay(std::time::Duration::from_secs(1)),
+        }
+    }
+}
+
+impl ReadDecideAppendOptions {
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// How many times, and how fast, the command is retried when the stream was written to
+    /// concurrently. Default: 3 retries, starting at 50ms and doubling up to 1s.
+    pub fn retry_options(self, retry: RetryOptions) -> Self {
+        Self { retry, ..self }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::options::retry::RetryOptions;
 use crate::{Credentials, ReadDirection, StreamPosition};
 
 #[derive(Clone)]
@@ -6,6 +7,7 @@ pub struct ReadStreamOptions {
     pub(crate) direction: ReadDirection,
     pub(crate) position: StreamPosition<u64>,
     pub(crate) resolve_link_tos: bool,
+    pub(crate) retry: Option<RetryOptions>,
 }
 
 impl Default for ReadStreamOptions {
@@ -15,6 +17,7 @@ impl Default for ReadStreamOptions {
             direction: ReadDirection::Forward,
             position: StreamPosition::Start,
             resolve_link_tos: false,
+            retry: None,
         }
     }
 }
@@ -73,4 +76,14 @@ impl ReadStreamOptions {
             ..self
         }
     }
+
+    /// Retries the read when it fails with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy. Only the call establishing the
+    /// read is retried, not the consumption of the returned stream.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
 }
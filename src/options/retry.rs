@@ -3,6 +3,9 @@
 pub struct RetryOptions {
     pub(crate) limit: usize,
     pub(crate) delay: std::time::Duration,
+    pub(crate) backoff_factor: u32,
+    pub(crate) max_delay: std::time::Duration,
+    pub(crate) jitter: bool,
 }
 
 impl Default for RetryOptions {
@@ -10,12 +13,16 @@ impl Default for RetryOptions {
         Self {
             limit: 3,
             delay: std::time::Duration::from_millis(500),
+            backoff_factor: 1,
+            max_delay: std::time::Duration::from_secs(30),
+            jitter: false,
         }
     }
 }
 
 impl RetryOptions {
-    /// Sets how many time we retry a failing command before giving up.
+    /// Sets how many time we retry a failing command before giving up. The command is attempted
+    /// at most `limit + 1` times, a limit of 0 means it's never retried.
     pub fn retry_limit(self, limit: usize) -> Self {
         Self { limit, ..self }
     }
@@ -29,4 +36,92 @@ impl RetryOptions {
     pub fn retry_delay(self, delay: std::time::Duration) -> Self {
         Self { delay, ..self }
     }
+
+    /// Multiplies the retry delay by the given factor after every failed attempt. Default: 1,
+    /// meaning the delay stays the same.
+    pub fn retry_backoff(self, backoff_factor: u32) -> Self {
+        Self {
+            backoff_factor: backoff_factor.max(1),
+            ..self
+        }
+    }
+
+    /// Caps the delay between two attempts when using a backoff factor. Default: 30s.
+    pub fn retry_max_delay(self, max_delay: std::time::Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Randomizes every delay between half and all of its computed value, so clients that
+    /// failed at the same time don't all retry at the same time. Default: false.
+    pub fn retry_jitter(self, jitter: bool) -> Self {
+        Self { jitter, ..self }
+    }
+
+    /// Tells if a command that has already been retried `retries` times can be retried again.
+    pub(crate) fn can_retry(&self, retries: usize) -> bool {
+        retries < self.limit
+    }
+
+    /// Delay to wait before the given attempt, attempts starting at 0.
+    pub(crate) fn delay_for(&self, attempt: usize) -> std::time::Duration {
+        let factor = self
+            .backoff_factor
+            .checked_pow(attempt.min(u32::MAX as usize) as u32)
+            .unwrap_or(u32::MAX);
+
+        let delay = self
+            .delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+
+        if !self.jitter {
+            return delay;
+        }
+
+        let half = delay / 2;
+        half + half.mul_f64(rand::random::<f64>())
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::RetryOptions;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_is_capped_and_jittered() {
+        let retry = RetryOptions::default()
+            .retry_delay(Duration::from_millis(100))
+            .retry_backoff(2)
+            .retry_max_delay(Duration::from_secs(1));
+
+        assert_eq!(retry.delay_for(0), Duration::from_millis(100));
+        assert_eq!(retry.delay_for(3), Duration::from_millis(800));
+        assert_eq!(retry.delay_for(4), Duration::from_secs(1));
+        assert_eq!(retry.delay_for(usize::MAX), Duration::from_secs(1));
+
+        let retry = retry.retry_jitter(true);
+
+        for attempt in 0..6 {
+            let expected = retry.retry_jitter(false).delay_for(attempt);
+            let delay = retry.delay_for(attempt);
+
+            assert!(delay >= expected / 2 && delay <= expected);
+        }
+    }
+
+    #[test]
+    fn limit_counts_retries() {
+        let retry = RetryOptions::default().retry_limit(2);
+        let attempts = (0..)
+            .take_while(|retries| retry.can_retry(*retries))
+            .count()
+            + 1;
+
+        assert_eq!(attempts, 3);
+        assert!(!RetryOptions::default().retry_limit(0).can_retry(0));
+        assert!(RetryOptions::default()
+            .retry_forever()
+            .can_retry(usize::MAX - 1));
+    }
 }
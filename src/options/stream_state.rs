@@ -0,0 +1,45 @@
+use crate::options::read_stream::ReadStreamOptions;
+use crate::options::retry::RetryOptions;
+use crate::{Credentials, StreamPosition};
+
+#[derive(Clone, Default)]
+/// Options of the stream state command.
+pub struct StreamStateOptions {
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) retry: Option<RetryOptions>,
+}
+
+impl StreamStateOptions {
+    /// Performs the command with the given credentials.
+    pub fn authenticated(self, credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Retries the reads when they fail with a retryable error, like a leader change or a
+    /// connection reset. Overrides the client-wide retry policy.
+    pub fn retry_options(self, options: RetryOptions) -> Self {
+        Self {
+            retry: Some(options),
+            ..self
+        }
+    }
+
+    /// Options of the reads the command is made of: the last event of the stream, and its
+    /// metadata.
+    pub(crate) fn read_options(&self) -> ReadStreamOptions {
+        let mut options = ReadStreamOptions::default().position(StreamPosition::End);
+
+        if let Some(credentials) = self.credentials.clone() {
+            options = options.authenticated(credentials);
+        }
+
+        if let Some(retry) = self.retry {
+            options = options.retry_options(retry);
+        }
+
+        options
+    }
+}
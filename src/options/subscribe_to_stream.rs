@@ -50,7 +50,12 @@ impl SubscribeToStreamOptions {
     }
 
     /// When a disconnection happens, automatically resubscribe to stream changes. When enabled,
-    /// The client will keep track of the current subscription offset.
+    /// The client will keep track of the current subscription offset. Delays between attempts
+    /// follow the backoff and jitter of the retry options, both opt-in: `RetryOptions::default()`
+    /// waits the same delay every time, use `retry_backoff` and `retry_jitter` to spread
+    /// resubscriptions out. Attempts are only reset once an event is received. Once the retry
+    /// limit is reached, or when retrying can't help, `Error::SubscriptionDropped` is returned
+    /// with the drop reason.
     pub fn retry_options(self, options: RetryOptions) -> Self {
         Self {
             retry: Some(options),
@@ -5,6 +5,7 @@ use crate::options::projections::{
     CreateProjectionOptions, DeleteProjectionOptions, GetResultProjectionOptions,
     GetStateProjectionOptions, UpdateProjectionOptions,
 };
+use crate::options::retry::RetryOptions;
 use crate::Credentials;
 use futures::stream::BoxStream;
 use serde::de::DeserializeOwned;
@@ -52,6 +53,14 @@ impl ProjectionClient {
         ProjectionClient { client }
     }
 
+    /// Retries projection state and result reads failing with a retryable error, like a leader
+    /// change or a connection reset. Reads can override this policy with their own
+    /// `retry_options`. Default: no retry.
+    pub fn with_retry_options(mut self, options: RetryOptions) -> Self {
+        self.client.set_default_retry(Some(options));
+        self
+    }
+
     pub async fn create<Name>(
         &self,
         name: Name,
@@ -435,22 +444,28 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let mut req = tonic::Request::new(req);
-
-        crate::commands::configure_auth_req(&mut req, options.credentials.as_ref().cloned());
-
         self.client
-            .execute(|handle| async move {
-                let mut client =
-                    projections::projections_client::ProjectionsClient::new(handle.channel.clone());
-
-                let resp = client.state(req).await?.into_inner();
-                let value = resp
-                    .state
-                    .map(parse_value)
-                    .unwrap_or(serde_json::Value::Null);
-
-                Ok(serde_json::from_value(value))
+            .execute_with_retry(options.retry.as_ref(), |handle| {
+                let mut req = tonic::Request::new(req.clone());
+
+                crate::commands::configure_auth_req(
+                    &mut req,
+                    options.credentials.as_ref().cloned(),
+                );
+
+                async move {
+                    let mut client = projections::projections_client::ProjectionsClient::new(
+                        handle.channel.clone(),
+                    );
+
+                    let resp = client.state(req).await?.into_inner();
+                    let value = resp
+                        .state
+                        .map(parse_value)
+                        .unwrap_or(serde_json::Value::Null);
+
+                    Ok(serde_json::from_value(value))
+                }
             })
             .await
     }
@@ -473,22 +488,28 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let mut req = tonic::Request::new(req);
-
-        crate::commands::configure_auth_req(&mut req, options.credentials.as_ref().cloned());
-
         self.client
-            .execute(|handle| async move {
-                let mut client =
-                    projections::projections_client::ProjectionsClient::new(handle.channel.clone());
-
-                let resp = client.result(req).await?.into_inner();
-                let value = resp
-                    .result
-                    .map(parse_value)
-                    .unwrap_or(serde_json::Value::Null);
-
-                Ok(serde_json::from_value(value))
+            .execute_with_retry(options.retry.as_ref(), |handle| {
+                let mut req = tonic::Request::new(req.clone());
+
+                crate::commands::configure_auth_req(
+                    &mut req,
+                    options.credentials.as_ref().cloned(),
+                );
+
+                async move {
+                    let mut client = projections::projections_client::ProjectionsClient::new(
+                        handle.channel.clone(),
+                    );
+
+                    let resp = client.result(req).await?.into_inner();
+                    let value = resp
+                        .result
+                        .map(parse_value)
+                        .unwrap_or(serde_json::Value::Null);
+
+                    Ok(serde_json::from_value(value))
+                }
             })
             .await
     }
@@ -0,0 +1,888 @@
+//! Replicates the events of a source cluster into a target cluster.
+//!
+//! The [`Replicator`] subscribes to `$all` on the source and appends every user event, along
+//! with the `$$` metadata streams, to the target. Events keep their ids and metadata. Progress
+//! is checkpointed into a stream of the target, so a replicator can be stopped and started
+//! again without losing nor duplicating events.
+//!
+//! Consecutive events of a stream are appended together. Target streams are numbered from 0, so
+//! if the source was truncated or scavenged, the `$tb` of replicated metadata is rewritten to
+//! the matching target revision.
+//!
+//! ```no_run
+//! # use eventstore::{Client, Replicator, ReplicatorOptions};
+//! # async fn doc(source: Client, target: Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let replicator = Replicator::new(source, target);
+//! let options = ReplicatorOptions::default()
+//!     .stream_prefix("order-")
+//!     .rename("order-", "legacy-order-");
+//!
+//! // Runs until the source subscription ends.
+//! replicator.run(&options).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::batch::Chunker;
+use crate::{
+    AppendToStreamOptions, ArchivedEvent, Client, Credentials, EventData, Position, ReadResult,
+    ReadStreamOptions, RecordedEvent, RetryOptions, StreamPosition, SubEvent,
+    SubscribeToAllOptions, SubscriptionFilter, TombstoneStreamOptions, UpdateStreamMetadataOptions,
+};
+
+/// Stream of the target in which the replicator stores its checkpoint by default.
+pub const DEFAULT_REPLICATION_CHECKPOINT_STREAM: &str = "replicator-checkpoint";
+
+const CHECKPOINT_EVENT_TYPE: &str = "replicator-checkpoint";
+const STREAM_DELETED_EVENT_TYPE: &str = "$streamDeleted";
+const TRUNCATE_BEFORE_KEY: &str = "$tb";
+
+// Byte budget of a single append to the target, well under the server default maximum append
+// size.
+const BATCH_SIZE: usize = 512 * 1_024;
+
+#[derive(Debug, Error)]
+pub enum ReplicationError {
+    #[error("Source error: {0}")]
+    Source(crate::Error),
+    #[error("Error when writing to {stream_name} on the target: {error}")]
+    Target {
+        stream_name: String,
+        error: crate::Error,
+    },
+    #[error("Malformed checkpoint: {0}")]
+    Checkpoint(serde_json::Error),
+    #[error("Cannot serialize checkpoint: {0}")]
+    CheckpointSerialization(serde_json::Error),
+    /// The metadata of a stream truncates it, but none of the events on the target could be
+    /// found on the source, so the matching target revision is unknown.
+    #[error("Cannot map the truncation of {stream_name} to a target revision")]
+    Truncation { stream_name: String },
+}
+
+/// How events are selected and renamed, and where progress is recorded.
+#[derive(Clone)]
+pub struct ReplicatorOptions {
+    prefixes: Vec<String>,
+    renames: Vec<(String, String)>,
+    checkpoint_stream: String,
+    checkpoint_interval: usize,
+    stop_at: Option<Position>,
+    retry: Option<RetryOptions>,
+    source_credentials: Option<Credentials>,
+    target_credentials: Option<Credentials>,
+}
+
+impl Default for ReplicatorOptions {
+    fn default() -> Self {
+        Self {
+            prefixes: Vec::new(),
+            renames: Vec::new(),
+            checkpoint_stream: DEFAULT_REPLICATION_CHECKPOINT_STREAM.to_string(),
+            checkpoint_interval: 100,
+            stop_at: None,
+            retry: None,
+            source_credentials: None,
+            target_credentials: None,
+        }
+    }
+}
+
+impl ReplicatorOptions {
+    /// Only replicates the streams starting with the given prefix, along with their metadata
+    /// streams. Can be called several times, a stream being replicated if it matches any
+    /// prefix. By default, every user stream is replicated.
+    pub fn stream_prefix(mut self, prefix: impl AsRef<str>) -> Self {
+        self.prefixes.push(prefix.as_ref().to_string());
+        self
+    }
+
+    /// Replaces the `from` prefix of a stream name by `to` when writing to the target. Rules
+    /// are tried in the order they were added, the first matching one being applied.
+    pub fn rename(mut self, from: impl AsRef<str>, to: impl AsRef<str>) -> Self {
+        self.renames
+            .push((from.as_ref().to_string(), to.as_ref().to_string()));
+        self
+    }
+
+    /// Stream of the target holding the checkpoint. Default: `replicator-checkpoint`. Two
+    /// replicators writing to the same target must use different checkpoint streams. A system
+    /// stream, starting with `$`, requires the target credentials to be an admin's.
+    pub fn checkpoint_stream(self, stream_name: impl AsRef<str>) -> Self {
+        Self {
+            checkpoint_stream: stream_name.as_ref().to_string(),
+            ..self
+        }
+    }
+
+    /// How many events are replicated between two checkpoints. Default: 100.
+    pub fn checkpoint_interval(self, checkpoint_interval: usize) -> Self {
+        Self {
+            checkpoint_interval: checkpoint_interval.max(1),
+            ..self
+        }
+    }
+
+    /// Stops once the source subscription reaches the given position. By default, the
+    /// replicator keeps following the source.
+    pub fn stop_at(self, position: Position) -> Self {
+        Self {
+            stop_at: Some(position),
+            ..self
+        }
+    }
+
+    /// Resubscribes to the source when the subscription drops.
+    pub fn retry_options(self, retry: RetryOptions) -> Self {
+        Self {
+            retry: Some(retry),
+            ..self
+        }
+    }
+
+    /// Credentials used to subscribe to the source.
+    pub fn source_credentials(self, credentials: Credentials) -> Self {
+        Self {
+            source_credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Credentials used to write to the target.
+    pub fn target_credentials(self, credentials: Credentials) -> Self {
+        Self {
+            target_credentials: Some(credentials),
+            ..self
+        }
+    }
+
+    /// Name of the target stream an event of the given source stream is written to, or
+    /// `None` if the stream isn't replicated.
+    fn target_stream_name(&self, stream_name: &str) -> Option<String> {
+        let (metadata, name) = match stream_name.strip_prefix("$$") {
+            Some(name) => (true, name),
+            None => (false, stream_name),
+        };
+
+        if name.starts_with('$') || name == self.checkpoint_stream {
+            return None;
+        }
+
+        if !self.prefixes.is_empty() && !self.prefixes.iter().any(|p| name.starts_with(p)) {
+            return None;
+        }
+
+        let name = self
+            .renames
+            .iter()
+            .find_map(|(from, to)| {
+                name.strip_prefix(from.as_str())
+                    .map(|id| format!("{}{}", to, id))
+            })
+            .unwrap_or_else(|| name.to_string());
+
+        if metadata {
+            Some(format!("$${}", name))
+        } else {
+            Some(name)
+        }
+    }
+
+    fn filter(&self) -> Option<SubscriptionFilter> {
+        if self.prefixes.is_empty() {
+            return None;
+        }
+
+        let filter =
+            self.prefixes
+                .iter()
+                .fold(SubscriptionFilter::on_stream_name(), |filter, prefix| {
+                    filter
+                        .add_prefix(prefix)
+                        .add_prefix(format!("$${}", prefix))
+                });
+
+        Some(filter)
+    }
+
+    fn append_options(&self) -> AppendToStreamOptions {
+        let options = AppendToStreamOptions::default();
+
+        match self.target_credentials.clone() {
+            Some(credentials) => options.authenticated(credentials),
+            None => options,
+        }
+    }
+
+    fn read_options(&self) -> ReadStreamOptions {
+        let options = ReadStreamOptions::default();
+
+        match self.target_credentials.clone() {
+            Some(credentials) => options.authenticated(credentials),
+            None => options,
+        }
+    }
+
+    fn source_read_options(&self) -> ReadStreamOptions {
+        let options = ReadStreamOptions::default();
+
+        match self.source_credentials.clone() {
+            Some(credentials) => options.authenticated(credentials),
+            None => options,
+        }
+    }
+}
+
+/// Outcome of a replication run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplicationResult {
+    /// How many events were appended to the target.
+    pub replicated: usize,
+    /// How many events were already on the target and were skipped.
+    pub skipped: usize,
+    /// Last checkpointed position.
+    pub checkpoint: Option<Position>,
+}
+
+/// Consecutive events of a stream waiting to be appended to the target.
+struct Batch {
+    stream_name: String,
+    chunker: Chunker,
+    last_revision: u64,
+}
+
+impl Batch {
+    fn new(stream_name: String) -> Self {
+        Self {
+            stream_name,
+            chunker: Chunker::new(BATCH_SIZE),
+            last_revision: 0,
+        }
+    }
+
+    /// Adds an event. Returns the previous chunk and the source revision of its last event if
+    /// the event didn't fit in it.
+    fn push(&mut self, event: RecordedEvent) -> Option<(Vec<EventData>, u64)> {
+        let previous = std::mem::replace(&mut self.last_revision, event.revision);
+
+        self.chunker
+            .push(EventData::from(ArchivedEvent::from(event)))
+            .map(|chunk| (chunk, previous))
+    }
+
+    fn take(&mut self) -> (Vec<EventData>, u64) {
+        (self.chunker.take(), self.last_revision)
+    }
+}
+
+/// What the target already holds of a stream written to since the last checkpoint.
+enum Replicated {
+    /// Ids of the latest events of the stream.
+    Latest(HashSet<Uuid>),
+    /// The stream was tombstoned, so every one of its events was already replicated.
+    Deleted,
+}
+
+impl Replicated {
+    fn contains(&self, id: &Uuid) -> bool {
+        match self {
+            Replicated::Latest(ids) => ids.contains(id),
+            Replicated::Deleted => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    commit_position: u64,
+    prepare_position: u64,
+}
+
+/// Replicates the events of a source `Client` into a target `Client`.
+#[derive(Clone)]
+pub struct Replicator {
+    source: Client,
+    target: Client,
+}
+
+impl Replicator {
+    pub fn new(source: Client, target: Client) -> Self {
+        Replicator { source, target }
+    }
+
+    /// Reads the last checkpoint stored in the target, if any.
+    pub async fn checkpoint(
+        &self,
+        options: &ReplicatorOptions,
+    ) -> Result<Option<Position>, ReplicationError> {
+        let read_options = options
+            .read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let event = self
+            .target
+            .read_stream(
+                options.checkpoint_stream.as_str(),
+                &read_options,
+                crate::Single,
+            )
+            .await
+            .map_err(|error| ReplicationError::Target {
+                stream_name: options.checkpoint_stream.clone(),
+                error,
+            })?;
+
+        let checkpoint = match event {
+            ReadResult::Ok(Some(event)) => event
+                .get_original_event()
+                .as_json::<Checkpoint>()
+                .map_err(ReplicationError::Checkpoint)?,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Position {
+            commit: checkpoint.commit_position,
+            prepare: checkpoint.prepare_position,
+        }))
+    }
+
+    /// Replicates events from the last checkpoint on. Returns when the source subscription
+    /// ends or, if set, once the `stop_at` position is reached.
+    ///
+    /// Events replicated after the last checkpoint get replicated again when resuming. To
+    /// avoid duplicates, the first time a stream is written to after a restart, the ids of
+    /// its latest events on the target are looked up and the events already there are skipped.
+    /// The events of a stream already tombstoned on the target are skipped altogether.
+    ///
+    /// Metadata truncating a stream (`$tb`) is rewritten to target revisions. After a restart,
+    /// the revision offset of a stream is found by looking up its last target event on the
+    /// source. If that event is no longer on the source, replication fails with
+    /// [`ReplicationError::Truncation`] rather than truncating the wrong events.
+    pub async fn run(
+        &self,
+        options: &ReplicatorOptions,
+    ) -> Result<ReplicationResult, ReplicationError> {
+        let mut checkpoint = self.checkpoint(options).await?;
+        let mut sub_options = SubscribeToAllOptions::default();
+
+        if let Some(position) = checkpoint {
+            sub_options = sub_options.position(StreamPosition::Position(position));
+        }
+
+        if let Some(filter) = options.filter() {
+            sub_options = sub_options.filter(filter);
+        }
+
+        if let Some(retry) = options.retry {
+            sub_options = sub_options.retry_options(retry);
+        }
+
+        if let Some(credentials) = options.source_credentials.clone() {
+            sub_options = sub_options.authenticated(credentials);
+        }
+
+        let mut result = ReplicationResult {
+            replicated: 0,
+            skipped: 0,
+            checkpoint,
+        };
+
+        if reached(checkpoint, options.stop_at) {
+            return Ok(result);
+        }
+
+        let mut sub = self
+            .source
+            .subscribe_to_all(&sub_options)
+            .await
+            .map_err(ReplicationError::Source)?;
+
+        // Streams written to since the last checkpoint are recovered the first time they are
+        // seen, as long as fewer events than the checkpoint interval were processed.
+        let mut recovering = checkpoint.is_some();
+        let mut recovered: HashMap<String, Replicated> = HashMap::new();
+        let mut processed = 0usize;
+        let mut since_checkpoint = 0usize;
+        let mut batch: Option<Batch> = None;
+        // Source revision minus target revision, per target stream.
+        let mut offsets: HashMap<String, i64> = HashMap::new();
+
+        while let Some(event) = sub.try_next().await.map_err(ReplicationError::Source)? {
+            let position = match event {
+                SubEvent::EventAppeared(event) => {
+                    let event = match event.event {
+                        Some(event) => event,
+                        None => continue,
+                    };
+
+                    let position = event.position;
+
+                    if let Some(stream_name) = options.target_stream_name(&event.stream_id) {
+                        processed += 1;
+                        since_checkpoint += 1;
+
+                        if recovering && processed > options.checkpoint_interval {
+                            recovering = false;
+                            recovered.clear();
+                        }
+
+                        let duplicate = if recovering {
+                            if !recovered.contains_key(&stream_name) {
+                                let replicated = self.replicated(&stream_name, options).await?;
+                                recovered.insert(stream_name.clone(), replicated);
+                            }
+
+                            recovered[&stream_name].contains(&event.id)
+                        } else {
+                            false
+                        };
+
+                        if duplicate {
+                            result.skipped += 1;
+                        } else {
+                            self.replicate(stream_name, event, &mut batch, &mut offsets, options)
+                                .await?;
+                            result.replicated += 1;
+                        }
+                    }
+
+                    if since_checkpoint >= options.checkpoint_interval {
+                        self.flush(&mut batch, &mut offsets, options).await?;
+                        self.store_checkpoint(position, checkpoint.is_none(), options)
+                            .await?;
+                        checkpoint = Some(position);
+                        since_checkpoint = 0;
+                    }
+
+                    position
+                }
+
+                SubEvent::Checkpoint(position) => {
+                    if checkpoint.map(|p| p.commit) < Some(position.commit) {
+                        self.flush(&mut batch, &mut offsets, options).await?;
+                        self.store_checkpoint(position, checkpoint.is_none(), options)
+                            .await?;
+                        checkpoint = Some(position);
+                        since_checkpoint = 0;
+                    }
+
+                    position
+                }
+
+                SubEvent::Confirmed(_) | SubEvent::CaughtUp | SubEvent::FellBehind => continue,
+            };
+
+            if reached(Some(position), options.stop_at) {
+                self.flush(&mut batch, &mut offsets, options).await?;
+
+                if checkpoint != Some(position) {
+                    self.store_checkpoint(position, checkpoint.is_none(), options)
+                        .await?;
+                    checkpoint = Some(position);
+                }
+
+                break;
+            }
+        }
+
+        self.flush(&mut batch, &mut offsets, options).await?;
+        result.checkpoint = checkpoint;
+
+        Ok(result)
+    }
+
+    async fn replicate(
+        &self,
+        stream_name: String,
+        event: RecordedEvent,
+        batch: &mut Option<Batch>,
+        offsets: &mut HashMap<String, i64>,
+        options: &ReplicatorOptions,
+    ) -> Result<(), ReplicationError> {
+        if batch
+            .as_ref()
+            .is_some_and(|batch| batch.stream_name != stream_name)
+            || event.event_type == STREAM_DELETED_EVENT_TYPE
+            || stream_name.starts_with("$$")
+        {
+            self.flush(batch, offsets, options).await?;
+        }
+
+        if event.event_type == STREAM_DELETED_EVENT_TYPE {
+            let mut tombstone_options = TombstoneStreamOptions::default();
+
+            if let Some(credentials) = options.target_credentials.clone() {
+                tombstone_options = tombstone_options.authenticated(credentials);
+            }
+
+            // The stream may have been tombstoned before the last checkpoint was stored.
+            return match self
+                .target
+                .tombstone_stream(stream_name.as_str(), &tombstone_options)
+                .await
+            {
+                Ok(_) | Err(crate::Error::StreamDeleted { .. }) => Ok(()),
+                Err(error) => Err(ReplicationError::Target { stream_name, error }),
+            };
+        }
+
+        if let Some(target_stream) = stream_name.strip_prefix("$$") {
+            let event = self
+                .rewrite_metadata(target_stream, event, offsets, options)
+                .await?;
+
+            return self
+                .append(&stream_name, vec![event], None, offsets, options)
+                .await;
+        }
+
+        let batch = batch.get_or_insert_with(|| Batch::new(stream_name));
+
+        if let Some((chunk, last_revision)) = batch.push(event) {
+            self.append(
+                &batch.stream_name,
+                chunk,
+                Some(last_revision),
+                offsets,
+                options,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(
+        &self,
+        batch: &mut Option<Batch>,
+        offsets: &mut HashMap<String, i64>,
+        options: &ReplicatorOptions,
+    ) -> Result<(), ReplicationError> {
+        let mut batch = match batch.take() {
+            Some(batch) => batch,
+            None => return Ok(()),
+        };
+
+        let (events, last_revision) = batch.take();
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        self.append(
+            &batch.stream_name,
+            events,
+            Some(last_revision),
+            offsets,
+            options,
+        )
+        .await
+    }
+
+    /// Appends events to the target. If given, `last_revision` is the source revision of the
+    /// last event, used to record the revision offset of the stream.
+    async fn append(
+        &self,
+        stream_name: &str,
+        events: Vec<EventData>,
+        last_revision: Option<u64>,
+        offsets: &mut HashMap<String, i64>,
+        options: &ReplicatorOptions,
+    ) -> Result<(), ReplicationError> {
+        let result = self
+            .target
+            .append_to_stream(stream_name, &options.append_options(), events)
+            .await
+            .map_err(|error| ReplicationError::Target {
+                stream_name: stream_name.to_string(),
+                error,
+            })?;
+
+        if let (Ok(result), Some(last_revision)) = (result, last_revision) {
+            offsets.insert(
+                stream_name.to_string(),
+                last_revision as i64 - result.next_expected_version as i64,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Rewrites the `$tb` of a metadata event from source to target revisions.
+    async fn rewrite_metadata(
+        &self,
+        target_stream: &str,
+        event: RecordedEvent,
+        offsets: &HashMap<String, i64>,
+        options: &ReplicatorOptions,
+    ) -> Result<EventData, ReplicationError> {
+        let mut event = ArchivedEvent::from(event);
+        let truncate_before = match truncate_before(&event.data) {
+            Some(truncate_before) => truncate_before,
+            None => return Ok(event.into()),
+        };
+
+        let offset = match offsets.get(target_stream) {
+            Some(offset) => Some(*offset),
+            None => {
+                let source_stream = event.stream_id.trim_start_matches("$$");
+                self.revision_offset(source_stream, target_stream, options)
+                    .await?
+            }
+        };
+
+        let target_truncate_before = match offset {
+            Some(offset) => (truncate_before as i64 - offset).max(0) as u64,
+            // Nothing of the stream is on the target yet, so there is nothing to truncate.
+            None => 0,
+        };
+
+        if let Some(data) = with_truncate_before(&event.data, target_truncate_before) {
+            event.data = data;
+        }
+
+        Ok(event.into())
+    }
+
+    /// Source revision minus target revision of the last event of a target stream, or `None`
+    /// if the target stream has no event.
+    async fn revision_offset(
+        &self,
+        source_stream: &str,
+        target_stream: &str,
+        options: &ReplicatorOptions,
+    ) -> Result<Option<i64>, ReplicationError> {
+        let read_options = options
+            .read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let last = self
+            .target
+            .read_stream(target_stream, &read_options, crate::Single)
+            .await
+            .map_err(|error| ReplicationError::Target {
+                stream_name: target_stream.to_string(),
+                error,
+            })?;
+
+        let last = match last {
+            ReadResult::Ok(Some(event)) => event.get_original_event().clone(),
+            _ => return Ok(None),
+        };
+
+        let read_options = options
+            .source_read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let events = self
+            .source
+            .read_stream(source_stream, &read_options, crate::All)
+            .await
+            .map_err(ReplicationError::Source)?;
+
+        if let ReadResult::Ok(mut events) = events {
+            while let Some(event) = events.try_next().await.map_err(ReplicationError::Source)? {
+                let event = event.get_original_event();
+
+                if event.id == last.id {
+                    return Ok(Some(event.revision as i64 - last.revision as i64));
+                }
+            }
+        }
+
+        Err(ReplicationError::Truncation {
+            stream_name: target_stream.to_string(),
+        })
+    }
+
+    async fn replicated(
+        &self,
+        stream_name: &str,
+        options: &ReplicatorOptions,
+    ) -> Result<Replicated, ReplicationError> {
+        let read_options = options
+            .read_options()
+            .position(StreamPosition::End)
+            .backwards();
+
+        let target_error = |error| ReplicationError::Target {
+            stream_name: stream_name.to_string(),
+            error,
+        };
+
+        // The metadata stream of a tombstoned stream can't be written to either.
+        if let Some(owner) = stream_name.strip_prefix("$$") {
+            let owner = self
+                .target
+                .read_stream(owner, &read_options, crate::Single)
+                .await
+                .map_err(target_error)?;
+
+            if let ReadResult::StreamDeleted(_) = owner {
+                return Ok(Replicated::Deleted);
+            }
+        }
+
+        let events = self
+            .target
+            .read_stream(stream_name, &read_options, options.checkpoint_interval)
+            .await
+            .map_err(target_error)?;
+
+        match events {
+            ReadResult::Ok(events) => events
+                .map_ok(|event| event.get_original_event().id)
+                .try_collect()
+                .await
+                .map(Replicated::Latest)
+                .map_err(target_error),
+
+            ReadResult::StreamDeleted(_) => Ok(Replicated::Deleted),
+            ReadResult::StreamNotFound(_) => Ok(Replicated::Latest(HashSet::new())),
+        }
+    }
+
+    async fn store_checkpoint(
+        &self,
+        position: Position,
+        first: bool,
+        options: &ReplicatorOptions,
+    ) -> Result<(), ReplicationError> {
+        let stream_name = options.checkpoint_stream.as_str();
+        let target_error = |error| ReplicationError::Target {
+            stream_name: stream_name.to_string(),
+            error,
+        };
+
+        // Only the latest checkpoint matters, older ones can be scavenged.
+        if first {
+            let mut metadata_options = UpdateStreamMetadataOptions::default();
+
+            if let Some(credentials) = options.target_credentials.clone() {
+                metadata_options = metadata_options.authenticated(credentials);
+            }
+
+            let _ = self
+                .target
+                .update_stream_metadata(stream_name, &metadata_options, |builder| {
+                    builder.max_count(1)
+                })
+                .await
+                .map_err(target_error)?
+                .map_err(|e| {
+                    target_error(crate::Error::WrongExpectedVersion {
+                        stream_name: format!("$${}", stream_name),
+                        expected: Some(e.expected),
+                        current: Some(e.current),
+                        code: None,
+                    })
+                })?;
+        }
+
+        let event = EventData::json(
+            CHECKPOINT_EVENT_TYPE,
+            Checkpoint {
+                commit_position: position.commit,
+                prepare_position: position.prepare,
+            },
+        )
+        .map_err(ReplicationError::CheckpointSerialization)?;
+
+        let _ = self
+            .target
+            .append_to_stream(stream_name, &options.append_options(), event)
+            .await
+            .map_err(target_error)?;
+
+        Ok(())
+    }
+}
+
+/// `$tb` of stream metadata, unless the stream was soft-deleted.
+fn truncate_before(metadata: &[u8]) -> Option<u64> {
+    let metadata: serde_json::Value = serde_json::from_slice(metadata).ok()?;
+
+    metadata
+        .get(TRUNCATE_BEFORE_KEY)?
+        .as_u64()
+        .filter(|revision| *revision < i64::MAX as u64)
+}
+
+fn with_truncate_before(metadata: &[u8], truncate_before: u64) -> Option<Bytes> {
+    let mut metadata: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(metadata).ok()?;
+
+    metadata.insert(TRUNCATE_BEFORE_KEY.to_string(), truncate_before.into());
+
+    serde_json::to_vec(&metadata).ok().map(Bytes::from)
+}
+
+fn reached(position: Option<Position>, stop_at: Option<Position>) -> bool {
+    match (position, stop_at) {
+        (Some(position), Some(stop_at)) => position.commit >= stop_at.commit,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod replication_tests {
+    use super::{truncate_before, with_truncate_before, ReplicatorOptions};
+
+    #[test]
+    fn map_source_streams_to_target_streams() {
+        let options = ReplicatorOptions::default()
+            .stream_prefix("order-")
+            .stream_prefix("invoice-")
+            .rename("order-", "legacy-order-");
+
+        assert_eq!(
+            options.target_stream_name("order-42").as_deref(),
+            Some("legacy-order-42")
+        );
+        assert_eq!(
+            options.target_stream_name("$$order-42").as_deref(),
+            Some("$$legacy-order-42")
+        );
+        assert_eq!(
+            options.target_stream_name("invoice-1").as_deref(),
+            Some("invoice-1")
+        );
+        assert_eq!(options.target_stream_name("customer-1"), None);
+        assert_eq!(options.target_stream_name("$ce-order"), None);
+        assert_eq!(options.target_stream_name("$$$ce-order"), None);
+
+        let options = ReplicatorOptions::default();
+
+        assert_eq!(
+            options.target_stream_name("customer-1").as_deref(),
+            Some("customer-1")
+        );
+        assert_eq!(options.target_stream_name("replicator-checkpoint"), None);
+    }
+
+    #[test]
+    fn rewrite_truncate_before() {
+        let metadata = br#"{"$maxCount":10,"$tb":42}"#;
+
+        assert_eq!(truncate_before(metadata), Some(42));
+        assert_eq!(truncate_before(br#"{"$maxCount":10}"#), None);
+        assert_eq!(truncate_before(br#"{"$tb":9223372036854775807}"#), None);
+        assert_eq!(truncate_before(b"not json"), None);
+
+        let rewritten = with_truncate_before(metadata, 2).unwrap();
+        let rewritten: serde_json::Value = serde_json::from_slice(&rewritten).unwrap();
+
+        assert_eq!(rewritten, serde_json::json!({ "$maxCount": 10, "$tb": 2 }));
+    }
+}
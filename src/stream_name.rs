@@ -0,0 +1,172 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Separator used by the `$by_category` system projection by default.
+pub const DEFAULT_CATEGORY_SEPARATOR: char = '-';
+
+/// Where a stream name is split to find its category, as configured on the `$by_category` system
+/// projection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CategorySplit {
+    /// At the first separator, the projection default. `shopping-cart-1` belongs to `shopping`.
+    #[default]
+    First,
+
+    /// At the last separator. `shopping-cart-1` belongs to `shopping-cart`.
+    Last,
+}
+
+impl CategorySplit {
+    /// Returns the category of a stream name, if it contains the separator.
+    pub fn category_of(self, stream_name: &str, separator: char) -> Option<&str> {
+        let split = match self {
+            CategorySplit::First => stream_name.split_once(separator),
+            CategorySplit::Last => stream_name.rsplit_once(separator),
+        };
+
+        split.map(|(category, _)| category)
+    }
+}
+
+/// A stream name, made of a category and an id separated by the first occurrence of a
+/// separator, `-` by default. `order-42` has `order` as category and `42` as id.
+///
+/// ```
+/// # use eventstore::StreamName;
+/// let name = StreamName::from_parts("order", "42");
+///
+/// assert_eq!(name.as_str(), "order-42");
+/// assert_eq!(name.category(), "order");
+/// assert_eq!(name.id(), Some("42"));
+///
+/// let name = StreamName::with_separator("order_42", '_');
+///
+/// assert_eq!(name.category(), "order");
+/// assert_eq!(name.id(), Some("42"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StreamName {
+    name: String,
+    separator: char,
+}
+
+impl StreamName {
+    /// Wraps a stream name, using `-` as separator.
+    pub fn new(name: impl AsRef<str>) -> Self {
+        StreamName::with_separator(name, DEFAULT_CATEGORY_SEPARATOR)
+    }
+
+    /// Wraps a stream name, using the given separator.
+    pub fn with_separator(name: impl AsRef<str>, separator: char) -> Self {
+        StreamName {
+            name: name.as_ref().to_string(),
+            separator,
+        }
+    }
+
+    /// Builds a stream name out of a category and an id, using `-` as separator.
+    pub fn from_parts(category: impl AsRef<str>, id: impl AsRef<str>) -> Self {
+        StreamName::from_parts_with_separator(category, id, DEFAULT_CATEGORY_SEPARATOR)
+    }
+
+    /// Builds a stream name out of a category and an id, using the given separator.
+    pub fn from_parts_with_separator(
+        category: impl AsRef<str>,
+        id: impl AsRef<str>,
+        separator: char,
+    ) -> Self {
+        StreamName {
+            name: format!("{}{}{}", category.as_ref(), separator, id.as_ref()),
+            separator,
+        }
+    }
+
+    /// The `$ce-{category}` stream, linking to the events of every stream of a category.
+    pub fn category_stream(category: impl AsRef<str>) -> Self {
+        StreamName::new(format!("$ce-{}", category.as_ref()))
+    }
+
+    /// The `$et-{event_type}` stream, linking to every event of a given type.
+    pub fn event_type_stream(event_type: impl AsRef<str>) -> Self {
+        StreamName::new(format!("$et-{}", event_type.as_ref()))
+    }
+
+    /// The `$bc-{correlation_id}` stream, linking to every event sharing a correlation id.
+    pub fn correlation_stream(correlation_id: impl AsRef<str>) -> Self {
+        StreamName::new(format!("$bc-{}", correlation_id.as_ref()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    /// The part of the name before the first separator, or the whole name if there is none.
+    pub fn category(&self) -> &str {
+        self.split()
+            .map_or(self.name.as_str(), |(category, _)| category)
+    }
+
+    /// The part of the name after the first separator, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.split().map(|(_, id)| id)
+    }
+
+    /// Tells whether this is a system stream, those starting with `$`.
+    pub fn is_system(&self) -> bool {
+        self.name.starts_with('$')
+    }
+
+    /// Tells whether this is a metadata stream, those starting with `$$`.
+    pub fn is_metadata(&self) -> bool {
+        self.name.starts_with("$$")
+    }
+
+    fn split(&self) -> Option<(&str, &str)> {
+        self.name.split_once(self.separator)
+    }
+}
+
+impl fmt::Display for StreamName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name.as_str())
+    }
+}
+
+impl AsRef<str> for StreamName {
+    fn as_ref(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl From<String> for StreamName {
+    fn from(name: String) -> Self {
+        StreamName {
+            name,
+            separator: DEFAULT_CATEGORY_SEPARATOR,
+        }
+    }
+}
+
+impl From<&str> for StreamName {
+    fn from(name: &str) -> Self {
+        StreamName::new(name)
+    }
+}
+
+impl From<StreamName> for String {
+    fn from(name: StreamName) -> Self {
+        name.name
+    }
+}
+
+impl FromStr for StreamName {
+    type Err = std::convert::Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(StreamName::new(name))
+    }
+}
@@ -4,6 +4,7 @@ use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::time::Duration;
 
+use crate::codec::{Codec, CodecError, CodecSet};
 use crate::gossip::VNodeState;
 use crate::private::Sealed;
 use async_trait::async_trait;
@@ -167,6 +168,55 @@ impl Ord for Position {
     }
 }
 
+/// Formats a position the way the server does, as `C:<commit>/P:<prepare>`.
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "C:{}/P:{}", self.commit, self.prepare)
+    }
+}
+
+/// Parses a position formatted as `C:<commit>/P:<prepare>`.
+impl std::str::FromStr for Position {
+    type Err = PositionParseError;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || PositionParseError(input.to_string());
+        let (commit, prepare) = input
+            .trim()
+            .strip_prefix("C:")
+            .and_then(|rest| rest.split_once("/P:"))
+            .ok_or_else(error)?;
+
+        Ok(Position {
+            commit: commit.parse().map_err(|_| error())?,
+            prepare: prepare.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_str(deserializer)
+    }
+}
+
+/// Returned when parsing a `Position` or a `StreamPosition` out of a malformed string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid position: `{0}`")]
+pub struct PositionParseError(String);
+
 /// Returned after writing to a stream.
 #[derive(Debug)]
 pub struct WriteResult {
@@ -177,6 +227,17 @@ pub struct WriteResult {
     pub position: Position,
 }
 
+/// Returned by commands that read a stream, then write to it expecting the revision that was
+/// read, like `Client::read_decide_append` and `Client::update_stream_metadata`.
+#[derive(Debug)]
+pub struct RetriedWriteResult {
+    /// Result of the write. `None` if there was nothing to write.
+    pub write_result: Option<WriteResult>,
+
+    /// How many times the read and the write were retried because of concurrent writes.
+    pub retries: usize,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum StreamPosition<A> {
     Start,
@@ -184,6 +245,71 @@ pub enum StreamPosition<A> {
     Position(A),
 }
 
+/// Formats as `start`, `end` or the position itself: a revision for a stream, or
+/// `C:<commit>/P:<prepare>` for `$all`.
+impl<A: std::fmt::Display> std::fmt::Display for StreamPosition<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamPosition::Start => f.write_str("start"),
+            StreamPosition::End => f.write_str("end"),
+            StreamPosition::Position(position) => position.fmt(f),
+        }
+    }
+}
+
+/// Parses `start`, `end` or a position, the way `Display` formats them.
+///
+/// ```
+/// # use eventstore::{Position, StreamPosition, SubscribeToAllOptions};
+/// let position: StreamPosition<Position> = "C:1024/P:1024".parse().unwrap();
+/// let options = SubscribeToAllOptions::default().position(position);
+///
+/// let revision: StreamPosition<u64> = "start".parse().unwrap();
+/// ```
+impl<A: std::str::FromStr> std::str::FromStr for StreamPosition<A> {
+    type Err = PositionParseError;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.trim() {
+            "start" => Ok(StreamPosition::Start),
+            "end" => Ok(StreamPosition::End),
+            position => position
+                .parse()
+                .map(StreamPosition::Position)
+                .map_err(|_| PositionParseError(input.to_string())),
+        }
+    }
+}
+
+impl<A: std::fmt::Display> Serialize for StreamPosition<A> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, A: std::str::FromStr> Deserialize<'de> for StreamPosition<A> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_str(deserializer)
+    }
+}
+
+fn parse_str<'de, D, A>(deserializer: D) -> std::result::Result<A, D::Error>
+where
+    D: Deserializer<'de>,
+    A: std::str::FromStr,
+    A::Err: std::fmt::Display,
+{
+    let input = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+
+    input.parse().map_err(serde::de::Error::custom)
+}
+
 /// Enumeration detailing the possible outcomes of reading a stream.
 #[derive(Debug)]
 pub enum ReadEventStatus<A> {
@@ -245,6 +371,49 @@ impl RecordedEvent {
     {
         serde_json::from_slice(&self.data[..])
     }
+
+    /// Returns the `content-type` this event was written with.
+    pub fn content_type(&self) -> Option<&str> {
+        self.metadata.get("content-type").map(String::as_str)
+    }
+
+    /// Decodes this event payload with the codec matching its stored `content-type`.
+    pub fn decode_with<T, C>(&self, codecs: &C) -> std::result::Result<T, CodecError>
+    where
+        C: CodecSet<T> + ?Sized,
+    {
+        let content_type = self.content_type().ok_or(CodecError::MissingContentType)?;
+        let codec = codecs
+            .codec_for(content_type)
+            .ok_or_else(|| CodecError::UnsupportedContentType(content_type.to_string()))?;
+
+        codec.decode(&self.data[..])
+    }
+
+    /// When the event was written, according to the server. The server stores it as a number of
+    /// 100-nanosecond ticks since the Unix epoch.
+    pub fn created(&self) -> Option<std::time::SystemTime> {
+        let ticks: u64 = self.metadata.get("created")?.parse().ok()?;
+
+        std::time::SystemTime::UNIX_EPOCH.checked_add(Duration::from_nanos(ticks.checked_mul(100)?))
+    }
+
+    /// Tells whether this event is a link, pointing to an event of another stream.
+    pub fn is_link(&self) -> bool {
+        self.event_type == LINK_EVENT_TYPE
+    }
+
+    /// If this event is a link, returns the stream and the revision of the event it points to.
+    pub fn link_target(&self) -> Option<(&str, u64)> {
+        if !self.is_link() {
+            return None;
+        }
+
+        let payload = std::str::from_utf8(&self.data[..]).ok()?;
+        let (revision, stream_name) = payload.split_once('@')?;
+
+        Some((stream_name, revision.parse().ok()?))
+    }
 }
 
 /// A structure representing a single event or an resolved link event.
@@ -266,6 +435,12 @@ impl ResolvedEvent {
         self.event.is_some() && self.link.is_some()
     }
 
+    /// If it's a link event whose target couldn't be resolved, because it was deleted or
+    /// truncated. Only the link is available then, and `ResolvedEvent::event` is `None`.
+    pub fn is_broken_link(&self) -> bool {
+        self.event.is_none() && self.link.is_some()
+    }
+
     /// Returns the event that was read or which triggered the subscription.
     /// If this `ResolvedEvent` represents a link event, the link will be the
     /// original event, otherwise it will be the event.
@@ -319,6 +494,45 @@ impl StreamMetadataResult {
     }
 }
 
+/// `$tb` value the server writes in a stream metadata when soft-deleting it.
+pub(crate) const SOFT_DELETED_TRUNCATE_BEFORE: u64 = i64::MAX as u64;
+
+/// State of a stream, combining its last event and its metadata.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StreamState {
+    /// The stream was never written to.
+    NotFound,
+    /// The stream was tombstoned: it can't be written to or recreated anymore.
+    Deleted,
+    /// The stream was soft-deleted, or truncated past its last event. Appending to it again
+    /// recreates it, its revisions continuing from where they left off.
+    SoftDeleted,
+    /// The stream has readable events.
+    Exists {
+        /// Revision of the last event of the stream.
+        last_revision: u64,
+        /// Position of the last event of the stream in the transaction file.
+        last_position: Position,
+        /// Revision before which events are no longer readable, if the stream was truncated.
+        truncate_before: Option<u64>,
+    },
+}
+
+impl StreamState {
+    pub fn exists(&self) -> bool {
+        matches!(self, StreamState::Exists { .. })
+    }
+
+    /// Revision of the last event of the stream, if it has readable events.
+    pub fn last_revision(&self) -> Option<u64> {
+        if let StreamState::Exists { last_revision, .. } = self {
+            return Some(*last_revision);
+        }
+
+        None
+    }
+}
+
 /// Represents a stream metadata.
 #[derive(Debug, Clone)]
 pub struct VersionedMetadata {
@@ -369,6 +583,9 @@ pub enum ReadStreamStatus<A> {
     Error(ReadStreamError),
 }
 
+/// Event type of the link events, pointing to an event of another stream.
+pub const LINK_EVENT_TYPE: &str = "$>";
+
 /// Holds data of event about to be sent to the server.
 #[derive(Clone, Debug)]
 pub struct EventData {
@@ -418,6 +635,49 @@ impl EventData {
         }
     }
 
+    /// Creates an event which payload is encoded with the given codec. The codec `content-type`
+    /// is stored along with the event, under the [`CONTENT_TYPE_METADATA_KEY`] key of the custom
+    /// metadata when the server can't keep it.
+    ///
+    /// [`CONTENT_TYPE_METADATA_KEY`]: crate::CONTENT_TYPE_METADATA_KEY
+    pub fn encode_with<S, P, C>(
+        event_type: S,
+        codec: &C,
+        payload: &P,
+    ) -> std::result::Result<EventData, CodecError>
+    where
+        S: AsRef<str>,
+        C: Codec<P> + ?Sized,
+    {
+        let payload = codec.encode(payload)?;
+        let mut metadata = HashMap::new();
+        metadata.insert("type".to_owned(), event_type.as_ref().to_owned());
+        metadata.insert("content-type".to_owned(), codec.content_type().to_owned());
+
+        Ok(EventData {
+            payload,
+            id_opt: None,
+            metadata,
+            custom_metadata: None,
+        })
+    }
+
+    /// Creates a link event pointing to the event at the given revision of a stream. Reading the
+    /// stream the link is written to with `resolve_link_tos` returns the event it points to.
+    pub fn link<S>(stream_name: S, revision: u64) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let payload = Bytes::from(format!("{}@{}", revision, stream_name.as_ref()));
+
+        EventData::binary(LINK_EVENT_TYPE, payload)
+    }
+
+    /// Creates a link event pointing to the given event.
+    pub fn link_to(event: &RecordedEvent) -> Self {
+        EventData::link(event.stream_id.as_str(), event.revision)
+    }
+
     /// Set an id to this event. By default, the id will be generated
     pub fn id(self, value: Uuid) -> Self {
         EventData {
@@ -445,6 +705,17 @@ impl EventData {
             ..self
         }
     }
+
+    /// Approximates how many bytes this event takes once sent to the server.
+    pub(crate) fn estimated_size(&self) -> usize {
+        // Event id and protobuf framing.
+        const OVERHEAD: usize = 64;
+
+        let metadata: usize = self.metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+        let custom_metadata = self.custom_metadata.as_ref().map_or(0, |b| b.len());
+
+        OVERHEAD + self.payload.len() + metadata + custom_metadata
+    }
 }
 
 /// Used to facilitate the creation of a stream's metadata.
@@ -526,6 +797,13 @@ impl StreamMetadataBuilder {
         self
     }
 
+    /// Removes a user-defined property from the stream metadata.
+    pub fn remove_custom_property(mut self, key: impl AsRef<str>) -> StreamMetadataBuilder {
+        let _ = self.properties.remove(key.as_ref());
+
+        self
+    }
+
     /// Returns a properly configured `StreamMetaData`.
     pub fn build(self) -> StreamMetadata {
         StreamMetadata {
@@ -539,6 +817,20 @@ impl StreamMetadataBuilder {
     }
 }
 
+/// Seeds a builder with existing metadata, custom properties included.
+impl From<StreamMetadata> for StreamMetadataBuilder {
+    fn from(metadata: StreamMetadata) -> Self {
+        StreamMetadataBuilder {
+            max_count: metadata.max_count,
+            max_age: metadata.max_age,
+            truncate_before: metadata.truncate_before,
+            cache_control: metadata.cache_control,
+            acl: metadata.acl,
+            properties: metadata.custom_properties,
+        }
+    }
+}
+
 /// Represents stream metadata with strongly types properties for system values
 /// and a dictionary-like interface for custom values.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
@@ -724,6 +1016,15 @@ pub struct StreamAclBuilder {
 }
 
 impl StreamAclBuilder {
+    /// Built-in role of the administrators.
+    pub const ADMINS: &'static str = "$admins";
+
+    /// Built-in role matching every user, authenticated or not.
+    pub const ALL: &'static str = "$all";
+
+    /// Built-in role of the operators.
+    pub const OPS: &'static str = "$ops";
+
     pub fn new() -> Self {
         StreamAclBuilder::default()
     }
@@ -777,6 +1078,14 @@ impl StreamAclBuilder {
             meta_write_roles: self.meta_write_roles,
         }
     }
+
+    /// Like `build` but fails if a role name is invalid. See `StreamAcl::validate`.
+    pub fn try_build(self) -> crate::Result<StreamAcl> {
+        let acl = self.build();
+        acl.validate()?;
+
+        Ok(acl)
+    }
 }
 
 /// Represents an access control list for a stream.
@@ -833,6 +1142,78 @@ pub struct StreamAcl {
     pub meta_write_roles: Option<Vec<String>>,
 }
 
+impl StreamAcl {
+    /// Checks every role name is valid: not blank, without surrounding whitespace, and if it
+    /// starts with `$`, one of the built-in roles (`$admins`, `$all` or `$ops`).
+    pub fn validate(&self) -> crate::Result<()> {
+        let roles = [
+            &self.read_roles,
+            &self.write_roles,
+            &self.delete_roles,
+            &self.meta_read_roles,
+            &self.meta_write_roles,
+        ];
+
+        for role in roles.iter().copied().flatten().flatten() {
+            if !is_valid_role(role) {
+                return Err(crate::Error::InvalidRole(role.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_valid_role(role: &str) -> bool {
+    if role.trim().is_empty() || role.trim() != role {
+        return false;
+    }
+
+    !role.starts_with('$')
+        || [
+            StreamAclBuilder::ADMINS,
+            StreamAclBuilder::ALL,
+            StreamAclBuilder::OPS,
+        ]
+        .contains(&role)
+}
+
+/// Server-wide default ACLs, stored in the `$settings` system stream. They apply to the streams
+/// that don't define their own ACL in their metadata.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SystemSettings {
+    /// Default ACL of user streams.
+    #[serde(
+        rename = "$userStreamAcl",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub user_stream_acl: Option<StreamAcl>,
+
+    /// Default ACL of system streams, the ones starting with `$`.
+    #[serde(
+        rename = "$systemStreamAcl",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub system_stream_acl: Option<StreamAcl>,
+}
+
+impl SystemSettings {
+    /// Checks the role names of both ACLs. See `StreamAcl::validate`.
+    pub fn validate(&self) -> crate::Result<()> {
+        for acl in self
+            .user_stream_acl
+            .iter()
+            .chain(self.system_stream_acl.iter())
+        {
+            acl.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
 fn serialize_roles<S>(
     src: &Option<Vec<String>>,
     serializer: S,
@@ -905,7 +1286,7 @@ where
 mod metadata_tests {
     use std::time::Duration;
 
-    use super::{Acl, StreamAclBuilder, StreamMetadata, StreamMetadataBuilder};
+    use super::{Acl, StreamAclBuilder, StreamMetadata, StreamMetadataBuilder, SystemSettings};
 
     #[test]
     fn isomorphic_1() -> Result<(), Box<dyn std::error::Error>> {
@@ -972,6 +1353,34 @@ mod metadata_tests {
         Ok(())
     }
 
+    #[test]
+    fn builder_seeded_from_metadata() {
+        let metadata = StreamMetadataBuilder::new()
+            .max_count(12)
+            .acl(Acl::UserStream)
+            .insert_custom_property("foo", "bar")
+            .insert_custom_property("baz", 42)
+            .build();
+
+        let actual = StreamMetadataBuilder::from(metadata.clone())
+            .max_age(Duration::from_secs(2))
+            .remove_custom_property("baz")
+            .build();
+
+        let expected = StreamMetadataBuilder::new()
+            .max_count(12)
+            .max_age(Duration::from_secs(2))
+            .acl(Acl::UserStream)
+            .insert_custom_property("foo", "bar")
+            .build();
+
+        assert_eq!(expected, actual);
+        assert_eq!(
+            metadata,
+            StreamMetadataBuilder::from(metadata.clone()).build()
+        );
+    }
+
     #[test]
     fn metadata_spec() -> Result<(), Box<dyn std::error::Error>> {
         let content = r#"
@@ -1014,6 +1423,65 @@ mod metadata_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn system_settings_spec() -> Result<(), Box<dyn std::error::Error>> {
+        let content = r#"
+        {
+            "$userStreamAcl": {
+                "$r": "$all",
+                "$w": ["$all", "ops-team"],
+                "$mw": "$admins"
+            },
+            "$systemStreamAcl": {
+                "$r": "$admins"
+            }
+        }
+        "#;
+
+        let expected = SystemSettings {
+            user_stream_acl: Some(
+                StreamAclBuilder::new()
+                    .add_read_roles(StreamAclBuilder::ALL)
+                    .add_write_roles(StreamAclBuilder::ALL)
+                    .add_write_roles("ops-team")
+                    .add_meta_write_roles(StreamAclBuilder::ADMINS)
+                    .build(),
+            ),
+            system_stream_acl: Some(
+                StreamAclBuilder::new()
+                    .add_read_roles(StreamAclBuilder::ADMINS)
+                    .build(),
+            ),
+        };
+
+        let actual: SystemSettings = serde_json::from_str(content)?;
+
+        assert_eq!(expected, actual);
+        assert!(actual.validate().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_role_names() {
+        let valid = StreamAclBuilder::new()
+            .add_read_roles(StreamAclBuilder::OPS)
+            .add_write_roles("admin")
+            .try_build();
+
+        assert!(valid.is_ok());
+
+        for role in ["", "  ", " admin", "$admin", "$everyone"] {
+            let result = StreamAclBuilder::new().add_read_roles(role).try_build();
+
+            assert!(
+                matches!(result, Err(crate::Error::InvalidRole(ref r)) if r == role),
+                "{:?} should be invalid",
+                role
+            );
+        }
+    }
 }
 
 /// Read part of a persistent subscription, isomorphic to a stream of events.
@@ -1046,6 +1514,56 @@ pub enum SubEvent<A> {
     /// Indicates a checkpoint has been created. Related to subscription to $all when
     /// filters are used.
     Checkpoint(Position),
+
+    /// The subscription went through every event written before it started and is now
+    /// receiving events as they are written. Sent again after a `FellBehind`.
+    CaughtUp,
+
+    /// The subscription was live but lagged too far behind, and is now catching up. Only sent
+    /// by servers supporting it.
+    FellBehind,
+}
+
+/// Why a catch-up subscription was dropped for good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionDropReason {
+    /// The subscription credentials don't allow reading the stream.
+    AccessDenied,
+
+    /// The stream was hard deleted.
+    StreamDeleted,
+
+    /// The server couldn't be reached or kept failing.
+    ServerUnavailable,
+
+    /// The client the subscription was created from was closed.
+    ClientClosed,
+
+    /// Any other error, see the cause for details.
+    Unknown,
+}
+
+impl SubscriptionDropReason {
+    /// Classifies the error that made a subscription drop.
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::AccessDenied { .. } => SubscriptionDropReason::AccessDenied,
+            Error::StreamDeleted { .. } => SubscriptionDropReason::StreamDeleted,
+            Error::ConnectionClosed => SubscriptionDropReason::ClientClosed,
+            Error::SubscriptionDropped { reason, .. } => *reason,
+            e if e.is_retryable() => SubscriptionDropReason::ServerUnavailable,
+            _ => SubscriptionDropReason::Unknown,
+        }
+    }
+
+    /// Indicates resubscribing has a chance to succeed. Access denied, deleted streams and
+    /// closed clients won't go away by retrying.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            SubscriptionDropReason::ServerUnavailable | SubscriptionDropReason::Unknown
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1320,20 +1838,20 @@ pub struct Endpoint {
 #[derive(Error, Debug, Clone)]
 /// EventStoreDB command error.
 pub enum Error {
-    #[error("Server-side error: {0}")]
-    ServerError(String),
+    #[error("Server-side error: {message}")]
+    ServerError { code: Option<Code>, message: String },
     #[error("You tried to execute a command that requires a leader node on a follower node. New leader: ")]
     NotLeaderException(Endpoint),
     #[error("Connection is closed.")]
     ConnectionClosed,
-    #[error("Unmapped gRPC error: {0}.")]
-    Grpc(String),
+    #[error("Unmapped gRPC error: {message}.")]
+    Grpc { code: Option<Code>, message: String },
     #[error("gRPC connection error: {0}")]
     GrpcConnectionError(GrpcConnectionError),
     #[error("Internal parsing error: {0}")]
     InternalParsingError(String),
     #[error("Access denied error")]
-    AccessDenied,
+    AccessDenied { code: Option<Code> },
     #[error("The resource you tried to create already exists")]
     ResourceAlreadyExists,
     #[error("The resource you asked for doesn't exist")]
@@ -1346,12 +1864,163 @@ pub enum Error {
     DeadlineExceeded,
     #[error("Initialization error: {0}")]
     InitializationError(String),
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+    #[error("Stream {stream_name} is deleted")]
+    StreamDeleted {
+        stream_name: String,
+        code: Option<Code>,
+    },
+    #[error("Maximum append size exceeded (max: {max_append_size:?} bytes)")]
+    MaximumAppendSizeExceeded {
+        max_append_size: Option<u32>,
+        code: Option<Code>,
+    },
+    #[error("Invalid transaction")]
+    InvalidTransaction { code: Option<Code> },
+    #[error(
+        "Wrong expected version on stream {stream_name}: expected: {expected:?}, got: {current:?}"
+    )]
+    WrongExpectedVersion {
+        stream_name: String,
+        expected: Option<ExpectedRevision>,
+        current: Option<CurrentRevision>,
+        code: Option<Code>,
+    },
+    #[error("User {login_name} not found")]
+    UserNotFound {
+        login_name: String,
+        code: Option<Code>,
+    },
+    #[error("Persistent subscription {group_name} on {stream_name} failed: {reason}")]
+    PersistentSubscriptionFailed {
+        stream_name: String,
+        group_name: String,
+        reason: String,
+        code: Option<Code>,
+    },
+    #[error(
+        "Maximum subscribers reached on persistent subscription {group_name} on {stream_name}"
+    )]
+    MaximumSubscribersReached {
+        stream_name: String,
+        group_name: String,
+        code: Option<Code>,
+    },
+    #[error("Persistent subscription {group_name} on {stream_name} was dropped")]
+    PersistentSubscriptionDropped {
+        stream_name: String,
+        group_name: String,
+        code: Option<Code>,
+    },
+    #[error("Invalid ACL role: {0:?}")]
+    InvalidRole(String),
+    #[error("System projection {0} is disabled or hasn't produced its stream yet")]
+    SystemProjectionUnavailable(String),
+    #[error("Checkpoint store error: {0}")]
+    CheckpointError(String),
+    #[error("Subscription dropped ({reason:?}): {cause}")]
+    SubscriptionDropped {
+        reason: SubscriptionDropReason,
+        cause: Box<Error>,
+    },
+}
+
+fn parse_expected_revision(value: i64) -> ExpectedRevision {
+    match value {
+        -1 => ExpectedRevision::NoStream,
+        -2 => ExpectedRevision::Any,
+        -4 => ExpectedRevision::StreamExists,
+        value => ExpectedRevision::Exact(value as u64),
+    }
+}
+
+fn parse_current_revision(value: i64) -> CurrentRevision {
+    if value < 0 {
+        CurrentRevision::NoStream
+    } else {
+        CurrentRevision::Current(value as u64)
+    }
 }
 
 impl Error {
+    /// Maps a server exception, reported through the `exception` metadata key, to a typed error.
+    fn from_exception(exception: &str, status: &Status) -> Option<Self> {
+        let metadata = status.metadata();
+        let get = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let get_or_default = |key: &str| get(key).unwrap_or_default();
+        let code = Some(status.code());
+
+        let error = match exception {
+            "stream-deleted" => Error::StreamDeleted {
+                stream_name: get_or_default("stream-name"),
+                code,
+            },
+
+            "maximum-append-size-exceeded" => Error::MaximumAppendSizeExceeded {
+                max_append_size: get("maximum-append-size").and_then(|value| value.parse().ok()),
+                code,
+            },
+
+            "invalid-transaction" => Error::InvalidTransaction { code },
+
+            "wrong-expected-version" => Error::WrongExpectedVersion {
+                stream_name: get_or_default("stream-name"),
+                expected: get("expected-version")
+                    .and_then(|value| value.parse().ok())
+                    .map(parse_expected_revision),
+                current: get("actual-version")
+                    .and_then(|value| value.parse().ok())
+                    .map(parse_current_revision),
+                code,
+            },
+
+            "user-not-found" => Error::UserNotFound {
+                login_name: get_or_default("login-name"),
+                code,
+            },
+
+            "persistent-subscription-failed" => Error::PersistentSubscriptionFailed {
+                stream_name: get_or_default("stream-name"),
+                group_name: get_or_default("group-name"),
+                reason: get("reason").unwrap_or_else(|| status.message().to_string()),
+                code,
+            },
+
+            "maximum-subscribers-reached" => Error::MaximumSubscribersReached {
+                stream_name: get_or_default("stream-name"),
+                group_name: get_or_default("group-name"),
+                code,
+            },
+
+            "persistent-subscription-dropped" => Error::PersistentSubscriptionDropped {
+                stream_name: get_or_default("stream-name"),
+                group_name: get_or_default("group-name"),
+                code,
+            },
+
+            _ => return None,
+        };
+
+        Some(error)
+    }
+
     pub fn from_grpc(status: Status) -> Self {
         let metadata = status.metadata();
-        if let Some("not-leader") = metadata.get("exception").and_then(|e| e.to_str().ok()) {
+        let exception = metadata.get("exception").and_then(|e| e.to_str().ok());
+
+        if let Some(error) =
+            exception.and_then(|exception| Error::from_exception(exception, &status))
+        {
+            return error;
+        }
+
+        if let Some("not-leader") = exception {
             let endpoint = metadata
                 .get("leader-endpoint-host")
                 .zip(metadata.get("leader-endpoint-port"))
@@ -1380,7 +2049,9 @@ impl Error {
         }
 
         if status.code() == Code::Unauthenticated || status.code() == Code::PermissionDenied {
-            return Error::AccessDenied;
+            return Error::AccessDenied {
+                code: Some(status.code()),
+            };
         }
 
         if status.code() == Code::AlreadyExists {
@@ -1395,14 +2066,68 @@ impl Error {
             || status.code() == Code::Internal
             || status.code() == Code::DataLoss
         {
-            return Error::ServerError(status.to_string());
+            return Error::ServerError {
+                code: Some(status.code()),
+                message: status.to_string(),
+            };
         }
 
         if status.code() == Code::Unimplemented {
             return Error::Unimplemented;
         }
 
-        Error::Grpc(status.to_string())
+        Error::Grpc {
+            code: Some(status.code()),
+            message: status.to_string(),
+        }
+    }
+
+    /// Returns the gRPC code of the status this error was built from. Variants that don't carry a
+    /// code return the code they stand for. Errors raised by the client itself return `None`.
+    pub fn code(&self) -> Option<Code> {
+        match self {
+            Error::ResourceAlreadyExists => Some(Code::AlreadyExists),
+            Error::ResourceNotFound => Some(Code::NotFound),
+            Error::Unimplemented => Some(Code::Unimplemented),
+            Error::DeadlineExceeded => Some(Code::DeadlineExceeded),
+            Error::StreamDeleted { code, .. }
+            | Error::MaximumAppendSizeExceeded { code, .. }
+            | Error::InvalidTransaction { code }
+            | Error::WrongExpectedVersion { code, .. }
+            | Error::UserNotFound { code, .. }
+            | Error::PersistentSubscriptionFailed { code, .. }
+            | Error::MaximumSubscribersReached { code, .. }
+            | Error::PersistentSubscriptionDropped { code, .. }
+            | Error::ServerError { code, .. }
+            | Error::Grpc { code, .. }
+            | Error::AccessDenied { code } => *code,
+            Error::SubscriptionDropped { cause, .. } => cause.code(),
+            Error::NotLeaderException(_)
+            | Error::ConnectionClosed
+            | Error::GrpcConnectionError(_)
+            | Error::InternalParsingError(_)
+            | Error::InternalClientError
+            | Error::InitializationError(_)
+            | Error::EncryptionError(_)
+            | Error::InvalidRole(_)
+            | Error::SystemProjectionUnavailable(_)
+            | Error::CheckpointError(_) => None,
+        }
+    }
+
+    /// Indicates the error is caused by the server or the network being temporarily unavailable,
+    /// like a server error or a connection reset, and is likely to go away on its own.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::ServerError { .. } | Error::GrpcConnectionError(_)
+        )
+    }
+
+    /// Indicates the command that failed can be sent again as-is. This covers transient errors
+    /// and leader redirections.
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient() || matches!(self, Error::NotLeaderException(_))
     }
 }
 
@@ -1637,3 +2362,236 @@ pub struct PersistentSubscriptionConfig {
     pub max_subscriber_count: i64,
     pub named_consumer_strategy: SystemConsumerStrategy,
 }
+
+#[cfg(test)]
+mod position_tests {
+    use super::{Position, StreamPosition};
+
+    #[test]
+    fn position_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let position = Position {
+            commit: 1_024,
+            prepare: 512,
+        };
+
+        assert_eq!(position.to_string(), "C:1024/P:512");
+        assert_eq!("C:1024/P:512".parse::<Position>()?, position);
+        assert_eq!(serde_json::to_string(&position)?, "\"C:1024/P:512\"");
+        assert_eq!(
+            serde_json::from_str::<Position>("\"C:1024/P:512\"")?,
+            position
+        );
+
+        assert!("1024/512".parse::<Position>().is_err());
+        assert!("C:1024/P:".parse::<Position>().is_err());
+        assert!("C:-1/P:0".parse::<Position>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_position_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let all: StreamPosition<Position> = "C:1/P:1".parse()?;
+
+        assert!(matches!(
+            all,
+            StreamPosition::Position(Position {
+                commit: 1,
+                prepare: 1
+            })
+        ));
+        assert!(matches!(
+            "start".parse::<StreamPosition<Position>>()?,
+            StreamPosition::Start
+        ));
+        assert!(matches!(
+            "end".parse::<StreamPosition<u64>>()?,
+            StreamPosition::End
+        ));
+        assert!(matches!(
+            "42".parse::<StreamPosition<u64>>()?,
+            StreamPosition::Position(42)
+        ));
+        assert!("C:1/P:1".parse::<StreamPosition<u64>>().is_err());
+
+        assert_eq!(StreamPosition::Position(42u64).to_string(), "42");
+        assert_eq!(StreamPosition::<u64>::End.to_string(), "end");
+        assert_eq!(serde_json::to_string(&all)?, "\"C:1/P:1\"");
+
+        let revision: StreamPosition<u64> = serde_json::from_str("\"start\"")?;
+
+        assert!(matches!(revision, StreamPosition::Start));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::{CurrentRevision, Error, ExpectedRevision, SubscriptionDropReason};
+    use tonic::{Code, Status};
+
+    fn status(code: Code, metadata: &[(&'static str, &'static str)]) -> Status {
+        let mut status = Status::new(code, "boom");
+
+        for (key, value) in metadata {
+            status.metadata_mut().insert(*key, value.parse().unwrap());
+        }
+
+        status
+    }
+
+    #[test]
+    fn map_server_exceptions() {
+        let error = Error::from_grpc(status(
+            Code::FailedPrecondition,
+            &[("exception", "stream-deleted"), ("stream-name", "foo")],
+        ));
+
+        assert!(
+            matches!(error, Error::StreamDeleted { ref stream_name, .. } if stream_name == "foo")
+        );
+        assert_eq!(error.code(), Some(Code::FailedPrecondition));
+
+        let error = Error::from_grpc(status(
+            Code::FailedPrecondition,
+            &[
+                ("exception", "wrong-expected-version"),
+                ("stream-name", "foo"),
+                ("expected-version", "-1"),
+                ("actual-version", "3"),
+            ],
+        ));
+
+        assert!(matches!(
+            error,
+            Error::WrongExpectedVersion {
+                expected: Some(ExpectedRevision::NoStream),
+                current: Some(CurrentRevision::Current(3)),
+                ..
+            }
+        ));
+
+        let error = Error::from_grpc(status(
+            Code::InvalidArgument,
+            &[
+                ("exception", "maximum-append-size-exceeded"),
+                ("maximum-append-size", "1024"),
+            ],
+        ));
+
+        assert!(matches!(
+            error,
+            Error::MaximumAppendSizeExceeded {
+                max_append_size: Some(1024),
+                code: Some(Code::InvalidArgument),
+            }
+        ));
+    }
+
+    #[test]
+    fn keep_grpc_code_and_classify() {
+        let error = Error::from_grpc(status(Code::ResourceExhausted, &[]));
+
+        assert!(matches!(
+            error,
+            Error::Grpc {
+                code: Some(Code::ResourceExhausted),
+                ..
+            }
+        ));
+        assert!(!error.is_retryable());
+
+        for code in [Code::Unavailable, Code::Internal, Code::DataLoss] {
+            let error = Error::from_grpc(status(code, &[]));
+            assert!(matches!(error, Error::ServerError { .. }));
+            assert_eq!(error.code(), Some(code));
+            assert!(error.is_transient());
+            assert!(error.is_retryable());
+        }
+
+        for code in [Code::PermissionDenied, Code::Unauthenticated] {
+            let error = Error::from_grpc(status(code, &[]));
+            assert!(matches!(error, Error::AccessDenied { .. }));
+            assert_eq!(error.code(), Some(code));
+        }
+
+        for code in [
+            Code::AlreadyExists,
+            Code::NotFound,
+            Code::Unimplemented,
+            Code::DeadlineExceeded,
+        ] {
+            assert_eq!(Error::from_grpc(status(code, &[])).code(), Some(code));
+        }
+
+        let error = Error::from_grpc(status(
+            Code::NotFound,
+            &[
+                ("exception", "not-leader"),
+                ("leader-endpoint-host", "localhost"),
+                ("leader-endpoint-port", "2113"),
+            ],
+        ));
+        assert!(!error.is_transient());
+        assert!(error.is_retryable());
+
+        assert!(!Error::from_grpc(status(Code::DeadlineExceeded, &[])).is_retryable());
+        assert!(!Error::ConnectionClosed.is_transient());
+        assert!(!Error::ConnectionClosed.is_retryable());
+
+        let error = Error::from_grpc(status(
+            Code::FailedPrecondition,
+            &[("exception", "invalid-transaction")],
+        ));
+        assert!(matches!(error, Error::InvalidTransaction { .. }));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn classify_subscription_drops() {
+        let reason = |error: Error| SubscriptionDropReason::from_error(&error);
+
+        assert_eq!(
+            reason(Error::from_grpc(status(
+                Code::PermissionDenied,
+                &[("exception", "access-denied")]
+            ))),
+            SubscriptionDropReason::AccessDenied
+        );
+        assert_eq!(
+            reason(Error::StreamDeleted {
+                stream_name: "foo".to_string(),
+                code: None,
+            }),
+            SubscriptionDropReason::StreamDeleted
+        );
+        assert_eq!(
+            reason(Error::from_grpc(status(Code::Unavailable, &[]))),
+            SubscriptionDropReason::ServerUnavailable
+        );
+        assert_eq!(
+            reason(Error::ConnectionClosed),
+            SubscriptionDropReason::ClientClosed
+        );
+        assert_eq!(
+            reason(Error::InternalParsingError("oops".to_string())),
+            SubscriptionDropReason::Unknown
+        );
+
+        assert!(SubscriptionDropReason::ServerUnavailable.is_recoverable());
+        assert!(!SubscriptionDropReason::AccessDenied.is_recoverable());
+        assert!(!SubscriptionDropReason::ClientClosed.is_recoverable());
+
+        let dropped = Error::SubscriptionDropped {
+            reason: SubscriptionDropReason::StreamDeleted,
+            cause: Box::new(Error::from_grpc(status(
+                Code::FailedPrecondition,
+                &[("exception", "stream-deleted")],
+            ))),
+        };
+
+        assert_eq!(dropped.code(), Some(Code::FailedPrecondition));
+        assert_eq!(reason(dropped), SubscriptionDropReason::StreamDeleted);
+    }
+}
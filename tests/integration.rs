@@ -51,6 +51,612 @@ async fn test_write_events(client: &Client) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+async fn test_retry_idempotent_operations(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("retry_idempotent_operations");
+    let client = client
+        .clone()
+        .with_retry_options(eventstore::RetryOptions::default().retry_limit(2));
+    let events = generate_events("retry-idempotent-operations", 3)
+        .into_iter()
+        .map(|event| event.id(uuid::Uuid::new_v4()))
+        .collect::<Vec<_>>();
+    let options = eventstore::AppendToStreamOptions::default().retry_options(
+        eventstore::RetryOptions::default()
+            .retry_delay(Duration::from_millis(10))
+            .retry_backoff(2),
+    );
+
+    let first = client
+        .append_to_stream(stream_id.as_str(), &options, events.clone())
+        .await?
+        .unwrap();
+
+    // Sending the same events again is deduplicated by the server, which is what makes retrying
+    // an append safe.
+    let second = client
+        .append_to_stream(stream_id.as_str(), &options, events)
+        .await?
+        .unwrap();
+
+    assert_eq!(first.next_expected_version, 2);
+    assert_eq!(second.next_expected_version, 2);
+
+    let options = eventstore::ReadStreamOptions::default()
+        .retry_options(eventstore::RetryOptions::default().retry_limit(1));
+    let read = client
+        .read_stream(stream_id.as_str(), &options, eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(read.len(), 3);
+
+    Ok(())
+}
+
+async fn test_list_streams(client: &Client) -> Result<(), Box<dyn Error>> {
+    let category = format!("liststreams{}", uuid::Uuid::new_v4().to_simple());
+    let mut expected = Vec::new();
+
+    for idx in 0..3 {
+        let stream_name = format!("{}-{}", category, idx);
+
+        let _ = client
+            .append_to_stream(
+                stream_name.as_str(),
+                &Default::default(),
+                generate_events("list-streams-test", 1),
+            )
+            .await?;
+
+        expected.push(stream_name);
+    }
+
+    let options = eventstore::ListStreamsOptions::default().category(category.as_str());
+
+    // The $streams projection indexes new streams asynchronously.
+    let names = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let names = client
+                .list_streams(&options)
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            if names.len() == expected.len() {
+                return Ok::<_, eventstore::Error>(names);
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    })
+    .await??;
+
+    assert_eq!(names, expected);
+
+    let page = client
+        .list_streams(&options.clone().skip(1).max_count(1))
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(page, vec![expected[1].clone()]);
+
+    let scanned = client
+        .list_streams(&options.clone().scan_all_fallback())
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(scanned, expected);
+
+    Ok(())
+}
+
+async fn test_read_category(client: &Client) -> Result<(), Box<dyn Error>> {
+    let category = format!("readcategory{}", uuid::Uuid::new_v4().to_simple());
+    let stream_name = eventstore::StreamName::from_parts(category.as_str(), "1");
+
+    assert_eq!(stream_name.category(), category);
+
+    let _ = client
+        .append_to_stream(
+            stream_name.as_str(),
+            &Default::default(),
+            generate_events("read-category-test", 2),
+        )
+        .await?;
+
+    // The $by_category projection writes its links asynchronously.
+    let events = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let ReadResult::Ok(stream) = client
+                .read_category(category.as_str(), &Default::default(), eventstore::All)
+                .await?
+            {
+                let events = stream.try_collect::<Vec<_>>().await?;
+
+                if events.len() == 2 {
+                    return Ok::<_, eventstore::Error>(events);
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    })
+    .await??;
+
+    for event in events {
+        let target = event.event.as_ref().expect("resolved event");
+        let link = event.link.as_ref().expect("link event");
+
+        assert_eq!(target.stream_id, stream_name.as_str());
+        assert_eq!(
+            link.stream_id,
+            eventstore::StreamName::category_stream(category.as_str()).as_str()
+        );
+    }
+
+    Ok(())
+}
+
+async fn test_codec_round_trip(client: &Client) -> Result<(), Box<dyn Error>> {
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Payload {
+        value: u32,
+    }
+
+    let stream_id = fresh_stream_id("codec");
+    let expected = Payload { value: 42 };
+    let mut events = Vec::new();
+
+    events.push(
+        EventData::encode_with("codec-test", &eventstore::Json, &expected)?
+            .metadata_as_json(json!({ "origin": "json" }))?,
+    );
+
+    #[cfg(feature = "msgpack")]
+    events.push(
+        EventData::encode_with("codec-test", &eventstore::MessagePack, &expected)?
+            .metadata_as_json(json!({ "origin": "msgpack" }))?,
+    );
+
+    #[cfg(feature = "cbor")]
+    events.push(
+        EventData::encode_with("codec-test", &eventstore::Cbor, &expected)?
+            .metadata(bytes::Bytes::from_static(b"cbor")),
+    );
+
+    let _ = client
+        .append_to_stream(stream_id.as_str(), &Default::default(), events.clone())
+        .await?;
+
+    let codecs = eventstore::Codecs::<Payload>::new().with(eventstore::Json);
+    #[cfg(feature = "msgpack")]
+    let codecs = codecs.with(eventstore::MessagePack);
+    #[cfg(feature = "cbor")]
+    let codecs = codecs.with(eventstore::Cbor);
+
+    let recorded = client
+        .read_stream(stream_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(recorded.len(), events.len());
+
+    for event in recorded {
+        let event = event.get_original_event();
+        let actual: Payload = event.decode_with(&codecs)?;
+
+        assert_eq!(actual, expected);
+        assert!(!event.custom_metadata.is_empty());
+        assert!(!event
+            .custom_metadata
+            .windows(eventstore::CONTENT_TYPE_METADATA_KEY.len())
+            .any(|w| w == eventstore::CONTENT_TYPE_METADATA_KEY.as_bytes()));
+    }
+
+    #[cfg(feature = "protobuf")]
+    {
+        let stream_id = fresh_stream_id("codec_protobuf");
+        let expected = prost_types::Duration {
+            seconds: 42,
+            nanos: 0,
+        };
+
+        let _ = client
+            .append_to_stream(
+                stream_id.as_str(),
+                &Default::default(),
+                EventData::encode_with("codec-test", &eventstore::Protobuf, &expected)?,
+            )
+            .await?;
+
+        let event = client
+            .read_stream(stream_id.as_str(), &Default::default(), Single)
+            .await?
+            .unwrap()
+            .expect("protobuf event");
+
+        let actual: prost_types::Duration = event
+            .get_original_event()
+            .decode_with(&eventstore::Protobuf)?;
+
+        assert_eq!(actual, expected);
+    }
+
+    Ok(())
+}
+
+async fn test_link_events(client: &Client) -> Result<(), Box<dyn Error>> {
+    let source = fresh_stream_id("link_source");
+    let index = fresh_stream_id("link_index");
+
+    let _ = client
+        .append_to_stream(
+            source.as_str(),
+            &Default::default(),
+            generate_events("link-test", 1),
+        )
+        .await?;
+
+    let target = client
+        .read_stream(source.as_str(), &Default::default(), Single)
+        .await?
+        .unwrap()
+        .expect("source event");
+
+    let _ = client
+        .append_to_stream(
+            index.as_str(),
+            &Default::default(),
+            EventData::link_to(target.get_original_event()),
+        )
+        .await?;
+
+    let options = eventstore::ReadStreamOptions::default().resolve_link_tos();
+    let resolved = client
+        .read_stream(index.as_str(), &options, Single)
+        .await?
+        .unwrap()
+        .expect("link event");
+
+    assert!(resolved.is_resolved());
+    assert_eq!(
+        resolved.event.as_ref().map(|e| e.id),
+        Some(target.get_original_event().id)
+    );
+    assert_eq!(
+        resolved.link.as_ref().and_then(|l| l.link_target()),
+        Some((source.as_str(), 0))
+    );
+
+    client
+        .delete_stream(source.as_str(), &Default::default())
+        .await?;
+
+    let broken = client
+        .read_stream(index.as_str(), &options, Single)
+        .await?
+        .unwrap()
+        .expect("link event");
+
+    assert!(broken.is_broken_link());
+
+    Ok(())
+}
+
+async fn test_replication(client: &Client) -> Result<(), Box<dyn Error>> {
+    let prefix = fresh_stream_id("replicated");
+    let stream_id = format!("{}-source", prefix);
+    let renamed_id = format!("{}-target", prefix);
+    let checkpoint_stream = format!("{}-checkpoint", prefix);
+    // Source and target are the same server here, so the renaming keeps them apart.
+    let replicator = eventstore::Replicator::new(client.clone(), client.clone());
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("replication-test", 5),
+        )
+        .await?;
+
+    let result = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("replication-test", 5),
+        )
+        .await?
+        .unwrap();
+
+    let options = eventstore::ReplicatorOptions::default()
+        .stream_prefix(stream_id.as_str())
+        .rename(stream_id.as_str(), renamed_id.as_str())
+        .checkpoint_stream(checkpoint_stream.as_str())
+        .checkpoint_interval(3)
+        .stop_at(result.position);
+
+    let replicated = replicator.run(&options).await?;
+
+    assert_eq!(replicated.replicated, 10);
+    assert!(replicated.checkpoint.is_some());
+
+    // Nothing left to replicate when resuming from the checkpoint.
+    let resumed = replicator.run(&options).await?;
+
+    assert_eq!(resumed.replicated, 0);
+
+    let source = client
+        .read_stream(stream_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let target = client
+        .read_stream(renamed_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(source.len(), target.len());
+
+    for (source, target) in source.iter().zip(target.iter()) {
+        let source = source.get_original_event();
+        let target = target.get_original_event();
+
+        assert_eq!(source.id, target.id);
+        assert_eq!(source.event_type, target.event_type);
+        assert_eq!(source.data, target.data);
+    }
+
+    Ok(())
+}
+
+async fn test_replication_resume(client: &Client) -> Result<(), Box<dyn Error>> {
+    let prefix = fresh_stream_id("replicated_resume");
+    let stream_id = format!("{}-source", prefix);
+    let renamed_id = format!("{}-target", prefix);
+    let checkpoint_stream = format!("{}-checkpoint", prefix);
+    let replicator = eventstore::Replicator::new(client.clone(), client.clone());
+
+    let result = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("replication-resume-test", 10),
+        )
+        .await?
+        .unwrap();
+
+    let options = eventstore::ReplicatorOptions::default()
+        .stream_prefix(stream_id.as_str())
+        .rename(stream_id.as_str(), renamed_id.as_str())
+        .checkpoint_stream(checkpoint_stream.as_str())
+        .checkpoint_interval(3)
+        .stop_at(result.position);
+
+    let replicated = replicator.run(&options).await?;
+
+    assert_eq!(replicated.replicated, 10);
+
+    let source = client
+        .read_stream(stream_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // Mimics a replicator stopped before checkpointing the last two events it replicated.
+    let older = source[7].get_original_event().position;
+    let checkpoint = |position: eventstore::Position| {
+        EventData::json(
+            "replicator-checkpoint",
+            json!({
+                "commitPosition": position.commit,
+                "preparePosition": position.prepare,
+            }),
+        )
+        .unwrap()
+    };
+
+    let _ = client
+        .append_to_stream(
+            checkpoint_stream.as_str(),
+            &Default::default(),
+            checkpoint(older),
+        )
+        .await?;
+
+    let resumed = replicator.run(&options).await?;
+
+    assert_eq!(resumed.replicated, 0);
+    assert_eq!(resumed.skipped, 2);
+
+    let target = client
+        .read_stream(renamed_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let ids = target
+        .iter()
+        .map(|event| event.get_original_event().id)
+        .collect::<std::collections::HashSet<_>>();
+
+    assert_eq!(target.len(), 10);
+    assert_eq!(ids.len(), 10);
+
+    // Same thing once the stream is tombstoned, its events and tombstone being replayed.
+    let deleted = client
+        .tombstone_stream(stream_id.as_str(), &Default::default())
+        .await?
+        .unwrap();
+
+    let options = options.stop_at(deleted);
+    let replicated = replicator.run(&options).await?;
+
+    assert_eq!(replicated.replicated, 1);
+
+    let _ = client
+        .append_to_stream(
+            checkpoint_stream.as_str(),
+            &Default::default(),
+            checkpoint(older),
+        )
+        .await?;
+
+    let resumed = replicator.run(&options).await?;
+
+    assert_eq!(resumed.replicated, 0);
+    assert_eq!(resumed.skipped, 3);
+
+    Ok(())
+}
+
+async fn test_export_import(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("export");
+    let ndjson_path = std::env::temp_dir().join(format!("{}.ndjson", stream_id));
+    let binary_path = std::env::temp_dir().join(format!("{}.bin", stream_id));
+    let exporter = eventstore::Exporter::new(client.clone());
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("export-test", 5),
+        )
+        .await?;
+
+    let filter = eventstore::SubscriptionFilter::on_stream_name().add_prefix(stream_id.as_str());
+    let options = eventstore::ExportOptions::default()
+        .format(eventstore::ExportFormat::Binary)
+        .filter(filter);
+
+    let result = exporter.export_all(&binary_path, &options).await?;
+
+    assert_eq!(result.exported, 5);
+    assert!(result.last_position.is_some());
+
+    let result = exporter
+        .export_streams(vec![stream_id.as_str()], &ndjson_path, &Default::default())
+        .await?;
+
+    assert_eq!(result.exported, 5);
+
+    // Events keep their ids, so importing them back into their stream is idempotent.
+    let result = exporter.import(&binary_path, &Default::default()).await?;
+
+    assert_eq!(result.imported, 5);
+
+    let result = exporter
+        .import(
+            &ndjson_path,
+            &eventstore::ImportOptions::default().skip(2).batch_append(),
+        )
+        .await?;
+
+    assert_eq!(result.imported, 3);
+    assert_eq!(result.skipped, 2);
+
+    let events = client
+        .read_stream(stream_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(events.len(), 5);
+
+    let _ = std::fs::remove_file(&ndjson_path);
+    let _ = std::fs::remove_file(&binary_path);
+
+    Ok(())
+}
+
+async fn test_archive_stream(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("archive");
+    let restored_id = fresh_stream_id("archive_restored");
+    let path = std::env::temp_dir().join(format!("{}.ndjson", stream_id));
+    let archiver = eventstore::Archiver::new(client.clone());
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("archive-test", 10),
+        )
+        .await?;
+
+    let result = archiver
+        .archive(
+            stream_id.as_str(),
+            &path,
+            &eventstore::ArchiveOptions::up_to_revision(4),
+        )
+        .await?;
+
+    assert_eq!(result.archived, 5);
+    assert_eq!(result.last_revision, Some(4));
+    assert_eq!(result.truncate_before, Some(5));
+
+    let remaining = client
+        .read_stream(stream_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    assert_eq!(remaining.len(), 5);
+    assert_eq!(remaining[0].get_original_event().revision, 5);
+
+    // Nothing left to archive, the existing archive is kept as is.
+    let result = archiver
+        .archive(
+            stream_id.as_str(),
+            &path,
+            &eventstore::ArchiveOptions::up_to_revision(4),
+        )
+        .await?;
+
+    assert_eq!(result.archived, 0);
+    assert_eq!(eventstore::read_archive(&path).await?.len(), 5);
+
+    let _ = archiver
+        .restore(&path, restored_id.as_str(), &Default::default())
+        .await?
+        .unwrap();
+
+    let restored = client
+        .read_stream(restored_id.as_str(), &Default::default(), eventstore::All)
+        .await?
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let archived = eventstore::read_archive(&path).await?;
+
+    assert_eq!(restored.len(), 5);
+
+    for (restored, archived) in restored.iter().zip(archived) {
+        let restored = restored.get_original_event();
+
+        assert_eq!(restored.id, archived.id);
+        assert_eq!(restored.data, archived.data);
+    }
+
+    let _ = std::fs::remove_file(&path);
+
+    Ok(())
+}
+
 // We read all stream events by batch.
 async fn test_read_all_stream_events(client: &Client) -> Result<(), Box<dyn Error>> {
     // Eventstore should always have "some" events in $all, since eventstore itself uses streams, ouroboros style.
@@ -59,6 +665,23 @@ async fn test_read_all_stream_events(client: &Client) -> Result<(), Box<dyn Erro
     Ok(())
 }
 
+async fn test_write_events_in_chunks(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("write_events_in_chunks");
+    let events = futures::stream::iter(generate_events("write-events-in-chunks", 10));
+    let options = eventstore::AppendToStreamOptions::default()
+        .expected_revision(eventstore::ExpectedRevision::NoStream)
+        .chunk_size(256);
+
+    let result = client
+        .append_to_stream(stream_id, &options, eventstore::Streaming::new(events))
+        .await?
+        .unwrap();
+
+    assert_eq!(result.next_expected_version, 9);
+
+    Ok(())
+}
+
 // We read stream events by batch. We also test if we can properly read a
 // stream thoroughly.
 async fn test_read_stream_events(client: &Client) -> Result<(), Box<dyn Error>> {
@@ -93,34 +716,127 @@ async fn test_read_stream_events(client: &Client) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
-async fn test_metadata(client: &Client) -> Result<(), Box<dyn Error>> {
-    let stream_id = fresh_stream_id("metadata");
-    let events = generate_events("metadata-test".to_string(), 5);
+async fn test_metadata(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("metadata");
+    let events = generate_events("metadata-test".to_string(), 5);
+
+    let _ = client
+        .append_to_stream(stream_id.as_str(), &Default::default(), events)
+        .await?;
+
+    let expected = StreamMetadataBuilder::new()
+        .max_age(std::time::Duration::from_secs(2))
+        .acl(Acl::Stream(
+            StreamAclBuilder::new().add_read_roles("admin").build(),
+        ))
+        .build();
+
+    let _ = client
+        .set_stream_metadata(stream_id.as_str(), &Default::default(), expected.clone())
+        .await?;
+
+    let actual = client
+        .get_stream_metadata(stream_id.as_str(), &Default::default())
+        .await?;
+
+    assert!(actual.is_success());
+
+    if let StreamMetadataResult::Success(actual) = actual {
+        assert_eq!(&expected, actual.metadata());
+    }
+
+    Ok(())
+}
+
+async fn test_update_stream_metadata(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("update_metadata");
+    let options = eventstore::UpdateStreamMetadataOptions::default();
+
+    let result = client
+        .update_stream_metadata(stream_id.as_str(), &options, |metadata| {
+            metadata
+                .max_count(10)
+                .insert_custom_property("owner", "billing")
+        })
+        .await?
+        .unwrap();
+
+    assert_eq!(
+        result.write_result.map(|r| r.next_expected_version),
+        Some(0)
+    );
+
+    client
+        .update_stream_metadata(stream_id.as_str(), &options, |metadata| {
+            metadata.max_age(Duration::from_secs(60))
+        })
+        .await?
+        .unwrap();
+
+    let expected = StreamMetadataBuilder::new()
+        .max_count(10)
+        .max_age(Duration::from_secs(60))
+        .insert_custom_property("owner", "billing")
+        .build();
+
+    let actual = client
+        .get_stream_metadata(
+            stream_id.as_str(),
+            &eventstore::ReadStreamOptions::default().position(eventstore::StreamPosition::End),
+        )
+        .await?;
+
+    if let StreamMetadataResult::Success(actual) = actual {
+        assert_eq!(actual.version(), 1);
+        assert_eq!(actual.metadata(), &expected);
+    } else {
+        panic!("Expected stream metadata");
+    }
+
+    Ok(())
+}
+
+async fn test_system_settings(client: &Client) -> Result<(), Box<dyn Error>> {
+    let acl = StreamAclBuilder::new()
+        .add_read_roles(StreamAclBuilder::ALL)
+        .add_write_roles(StreamAclBuilder::ALL)
+        .add_delete_roles(StreamAclBuilder::ALL)
+        .add_meta_read_roles(StreamAclBuilder::ALL)
+        .add_meta_write_roles(StreamAclBuilder::ALL)
+        .try_build()?;
+
+    let settings = eventstore::SystemSettings {
+        user_stream_acl: Some(acl),
+        system_stream_acl: Some(
+            StreamAclBuilder::new()
+                .add_read_roles(StreamAclBuilder::ADMINS)
+                .add_write_roles(StreamAclBuilder::ADMINS)
+                .add_delete_roles(StreamAclBuilder::ADMINS)
+                .add_meta_read_roles(StreamAclBuilder::ADMINS)
+                .add_meta_write_roles(StreamAclBuilder::ADMINS)
+                .try_build()?,
+        ),
+    };
 
-    let _ = client
-        .append_to_stream(stream_id.as_str(), &Default::default(), events)
-        .await?;
+    client
+        .set_system_settings(&Default::default(), settings.clone())
+        .await?
+        .unwrap();
 
-    let expected = StreamMetadataBuilder::new()
-        .max_age(std::time::Duration::from_secs(2))
-        .acl(Acl::Stream(
-            StreamAclBuilder::new().add_read_roles("admin").build(),
-        ))
-        .build();
+    let actual = client.get_system_settings(&Default::default()).await?;
 
-    let _ = client
-        .set_stream_metadata(stream_id.as_str(), &Default::default(), expected.clone())
-        .await?;
+    assert_eq!(actual, Some(settings));
 
-    let actual = client
-        .get_stream_metadata(stream_id.as_str(), &Default::default())
-        .await?;
+    let invalid = eventstore::SystemSettings {
+        user_stream_acl: Some(StreamAclBuilder::new().add_read_roles("$nobody").build()),
+        system_stream_acl: None,
+    };
 
-    assert!(actual.is_success());
+    let result = client
+        .set_system_settings(&Default::default(), invalid)
+        .await;
 
-    if let StreamMetadataResult::Success(actual) = actual {
-        assert_eq!(&expected, actual.metadata());
-    }
+    assert!(matches!(result, Err(eventstore::Error::InvalidRole(_))));
 
     Ok(())
 }
@@ -205,6 +921,55 @@ async fn test_tombstone_stream(client: &Client) -> Result<(), Box<dyn Error>> {
     }
 }
 
+// We check the state of a stream as it is created, soft-deleted and tombstoned.
+async fn test_stream_state(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("stream_state");
+    let options = eventstore::StreamStateOptions::default();
+
+    let state = client.stream_state(stream_id.as_str(), &options).await?;
+    assert_eq!(state, eventstore::StreamState::NotFound);
+
+    let result = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("stream-state-test", 3),
+        )
+        .await?
+        .unwrap();
+
+    let state = client.stream_state(stream_id.as_str(), &options).await?;
+    assert_eq!(state.last_revision(), Some(2));
+
+    let eventstore::StreamState::Exists {
+        last_position,
+        truncate_before,
+        ..
+    } = state
+    else {
+        panic!("expected an existing stream, got {:?}", state);
+    };
+
+    assert_eq!(last_position, result.position);
+    assert_eq!(truncate_before, None);
+
+    client
+        .delete_stream(stream_id.as_str(), &Default::default())
+        .await?;
+
+    let state = client.stream_state(stream_id.as_str(), &options).await?;
+    assert_eq!(state, eventstore::StreamState::SoftDeleted);
+
+    client
+        .tombstone_stream(stream_id.as_str(), &Default::default())
+        .await?;
+
+    let state = client.stream_state(stream_id.as_str(), &options).await?;
+    assert_eq!(state, eventstore::StreamState::Deleted);
+
+    Ok(())
+}
+
 // We write events into a stream. Then, we issue a catchup subscription. After,
 // we write another batch of events into the same stream. The goal is to make
 // sure we receive events written prior and after our subscription request.
@@ -265,6 +1030,238 @@ async fn test_subscription(client: &Client) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+async fn test_subscription_caught_up(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("caught_up");
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("caught-up-test", 3),
+        )
+        .await?;
+
+    let options = eventstore::SubscribeToStreamOptions::default()
+        .start_from(eventstore::StreamPosition::Start);
+
+    let mut sub = client
+        .subscribe_to_stream(stream_id.as_str(), &options)
+        .await?;
+
+    let mut seen = 0usize;
+
+    let caught_up = tokio::time::timeout(Duration::from_secs(60), async {
+        while let Some(event) = sub.try_next().await? {
+            match event {
+                eventstore::SubEvent::EventAppeared(_) => seen += 1,
+                eventstore::SubEvent::CaughtUp => return Ok(true),
+                _ => {}
+            }
+        }
+
+        Ok::<_, eventstore::Error>(false)
+    })
+    .await??;
+
+    assert!(caught_up);
+    assert_eq!(seen, 3);
+
+    Ok(())
+}
+
+async fn test_filtered_subscription_caught_up(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("filtered_caught_up");
+    let event_type = format!("filtered-caught-up-{}", uuid::Uuid::new_v4());
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events(event_type.as_str(), 3),
+        )
+        .await?;
+
+    // Unrelated events written afterwards never reach a filtered subscription.
+    let _ = client
+        .append_to_stream(
+            fresh_stream_id("filtered_caught_up_other").as_str(),
+            &Default::default(),
+            generate_events("filtered-caught-up-other", 3),
+        )
+        .await?;
+
+    let filter = eventstore::SubscriptionFilter::on_event_type().add_prefix(event_type.as_str());
+    let options = eventstore::SubscribeToAllOptions::default()
+        .position(eventstore::StreamPosition::Start)
+        .filter(filter);
+
+    let mut sub = client.subscribe_to_all(&options).await?;
+    let mut seen = 0usize;
+
+    let caught_up = tokio::time::timeout(Duration::from_secs(60), async {
+        while let Some(event) = sub.try_next().await? {
+            match event {
+                eventstore::SubEvent::EventAppeared(_) => seen += 1,
+                eventstore::SubEvent::CaughtUp => return Ok(true),
+                _ => {}
+            }
+        }
+
+        Ok::<_, eventstore::Error>(false)
+    })
+    .await??;
+
+    assert!(caught_up);
+    assert_eq!(seen, 3);
+
+    Ok(())
+}
+
+async fn test_subscription_dropped_reason(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("dropped_reason");
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("dropped-reason-test", 1),
+        )
+        .await?;
+
+    let _ = client
+        .tombstone_stream(stream_id.as_str(), &Default::default())
+        .await?;
+
+    let options = eventstore::SubscribeToStreamOptions::default()
+        .start_from(eventstore::StreamPosition::Start)
+        .retry_options(
+            eventstore::RetryOptions::default()
+                .retry_forever()
+                .retry_backoff(2)
+                .retry_jitter(true),
+        );
+
+    let mut sub = client
+        .subscribe_to_stream(stream_id.as_str(), &options)
+        .await?;
+
+    // Deleted streams aren't worth retrying, even when asked to retry forever.
+    let error = tokio::time::timeout(Duration::from_secs(60), async {
+        loop {
+            if let Err(e) = sub.try_next().await {
+                return e;
+            }
+        }
+    })
+    .await?;
+
+    assert!(matches!(
+        error,
+        eventstore::Error::SubscriptionDropped {
+            reason: eventstore::SubscriptionDropReason::StreamDeleted,
+            ..
+        }
+    ));
+
+    Ok(())
+}
+
+async fn test_subscription_with_checkpoints(client: &Client) -> Result<(), Box<dyn Error>> {
+    use eventstore::CheckpointStore;
+
+    let stream_id = fresh_stream_id("checkpointed");
+    let subscription_id = fresh_stream_id("checkpointed_sub");
+    let store = eventstore::StreamCheckpointStore::new(client.clone());
+    let checkpoint_options = eventstore::CheckpointOptions::default().every_events(2);
+    let options = eventstore::SubscribeToStreamOptions::default()
+        .start_from(eventstore::StreamPosition::Start);
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("checkpointed-test", 6),
+        )
+        .await?;
+
+    // Existing metadata of the checkpoint stream is kept when limiting it.
+    let checkpoint_stream = format!("checkpoint-{}", subscription_id);
+    let _ = client
+        .set_stream_metadata(
+            checkpoint_stream.as_str(),
+            &Default::default(),
+            StreamMetadataBuilder::new()
+                .insert_custom_property("owner", "billing")
+                .build(),
+        )
+        .await?;
+
+    let mut sub = client
+        .subscribe_to_stream_with_checkpoints(
+            stream_id.as_str(),
+            subscription_id.as_str(),
+            store.clone(),
+            &options,
+            &checkpoint_options,
+        )
+        .await?;
+
+    let mut count = 0usize;
+
+    // Processing 4 events then asking for the next one saves revision 3.
+    while count < 5 {
+        if let eventstore::SubEvent::EventAppeared(_) = sub.try_next().await?.unwrap() {
+            count += 1;
+        }
+    }
+
+    drop(sub);
+
+    assert_eq!(
+        store.load(subscription_id.as_str()).await?,
+        Some(eventstore::Checkpoint::Revision(3))
+    );
+
+    let metadata = client
+        .get_stream_metadata(
+            checkpoint_stream.as_str(),
+            &eventstore::ReadStreamOptions::default().position(eventstore::StreamPosition::End),
+        )
+        .await?;
+
+    if let StreamMetadataResult::Success(metadata) = metadata {
+        assert_eq!(
+            metadata.metadata(),
+            &StreamMetadataBuilder::new()
+                .max_count(1)
+                .insert_custom_property("owner", "billing")
+                .build()
+        );
+    } else {
+        panic!("Expected checkpoint stream metadata");
+    }
+
+    // Resuming starts right after the checkpoint.
+    let mut sub = client
+        .subscribe_to_stream_with_checkpoints(
+            stream_id.as_str(),
+            subscription_id.as_str(),
+            store,
+            &options,
+            &checkpoint_options,
+        )
+        .await?;
+
+    loop {
+        if let eventstore::SubEvent::EventAppeared(event) = sub.try_next().await?.unwrap() {
+            assert_eq!(event.get_original_event().revision, 4);
+            break;
+        }
+    }
+
+    Ok(())
+}
+
 async fn test_create_persistent_subscription(client: &Client) -> Result<(), Box<dyn Error>> {
     let stream_id = fresh_stream_id("create_persistent_sub");
 
@@ -440,7 +1437,10 @@ async fn test_persistent_subscription_to_all(
                     write
                         .ack_event(event.event)
                         .await
-                        .map_err(|e| eventstore::Error::Grpc(e.to_string()))?;
+                        .map_err(|e| eventstore::Error::Grpc {
+                            code: None,
+                            message: e.to_string(),
+                        })?;
 
                     count += 1;
 
@@ -687,6 +1687,164 @@ async fn test_batch_append(client: &Client) -> eventstore::Result<()> {
         assert_eq!(cpt, 3, "We expecting 3 events out of those streams");
     }
 
+    let options = eventstore::BatchAppendOptions::default().chunk_size(256);
+    let batch_client = client.batch_append(&options).await?;
+    let stream_id = fresh_stream_id("batch-append-chunked");
+    let result = batch_client
+        .append_to_stream(
+            stream_id.as_str(),
+            eventstore::ExpectedRevision::NoStream,
+            generate_events("batch-append-chunked-type", 10),
+        )
+        .await?
+        .unwrap();
+
+    assert_eq!(result.current_revision(), Some(9));
+
+    let result = batch_client
+        .append_to_stream(
+            stream_id.as_str(),
+            eventstore::ExpectedRevision::NoStream,
+            generate_events("batch-append-chunked-type", 1),
+        )
+        .await?;
+
+    assert!(matches!(
+        result,
+        Err(eventstore::BatchAppendError::WrongExpectedVersion(
+            eventstore::WrongExpectedVersion {
+                current: eventstore::CurrentRevision::Current(9),
+                expected: eventstore::ExpectedRevision::NoStream,
+            }
+        ))
+    ));
+
+    Ok(())
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct Counter {
+    value: u64,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Incremented;
+
+impl eventstore::Aggregate for Counter {
+    type Event = Incremented;
+    type Command = ();
+    type Error = std::convert::Infallible;
+
+    fn aggregate_type() -> &'static str {
+        "counter"
+    }
+
+    fn event_type(_: &Incremented) -> String {
+        "incremented".to_string()
+    }
+
+    fn apply(&mut self, _: Incremented) {
+        self.value += 1;
+    }
+
+    fn handle(&self, _: ()) -> Result<Vec<Incremented>, Self::Error> {
+        Ok(vec![Incremented])
+    }
+}
+
+async fn test_aggregate_repository(client: &Client) -> Result<(), Box<dyn Error>> {
+    let repository = eventstore::Repository::<Counter>::new(client.clone());
+    let id = uuid::Uuid::new_v4().to_string();
+
+    repository.execute(id.as_str(), ()).await?;
+    repository.execute(id.as_str(), ()).await?;
+
+    let mut first = repository.load(id.as_str()).await?;
+    let mut second = repository.load(id.as_str()).await?;
+
+    assert_eq!(first.state().value, 2);
+    assert_eq!(first.revision(), Some(1));
+
+    first.execute(())?;
+    second.execute(())?;
+
+    repository.save(&mut first).await?;
+
+    let result = repository.save(&mut second).await;
+
+    assert!(matches!(
+        result,
+        Err(eventstore::RepositoryError::Conflict { .. })
+    ));
+
+    Ok(())
+}
+
+async fn test_aggregate_snapshots(client: &Client) -> Result<(), Box<dyn Error>> {
+    let options = eventstore::SnapshotOptions::default().every(2);
+    let repository = eventstore::Repository::<Counter>::new(client.clone()).with_snapshots(options);
+    let id = uuid::Uuid::new_v4().to_string();
+
+    for _ in 0..5 {
+        repository.execute(id.as_str(), ()).await?;
+    }
+
+    let counter = repository.load(id.as_str()).await?;
+
+    assert_eq!(counter.state().value, 5);
+    assert_eq!(counter.revision(), Some(4));
+    assert_eq!(counter.snapshot_revision(), Some(3));
+
+    client
+        .delete_stream(format!("counter-{}", id), &Default::default())
+        .await?;
+
+    let mut counter = repository.load(id.as_str()).await?;
+
+    assert_eq!(counter.state().value, 0);
+    assert_eq!(counter.revision(), None);
+    assert_eq!(counter.snapshot_revision(), None);
+
+    counter.execute(())?;
+    repository.save(&mut counter).await?;
+
+    Ok(())
+}
+
+async fn test_read_decide_append(client: &Client) -> Result<(), Box<dyn Error>> {
+    let stream_id = fresh_stream_id("read-decide-append");
+    let mut interfered = false;
+
+    let result = client
+        .read_decide_append(stream_id.as_str(), &Default::default(), |events| {
+            let concurrent_write = !interfered;
+            interfered = true;
+            let stream_id = stream_id.clone();
+
+            async move {
+                // Simulates a concurrent writer on the first attempt.
+                if concurrent_write {
+                    client
+                        .append_to_stream(
+                            stream_id.as_str(),
+                            &Default::default(),
+                            generate_events("concurrent-write", 1),
+                        )
+                        .await?
+                        .unwrap();
+                }
+
+                let payload = json!({ "seen": events.len() });
+
+                Ok::<_, eventstore::Error>(vec![EventData::json("decided", payload).unwrap()])
+            }
+        })
+        .await?
+        .unwrap();
+
+    assert_eq!(result.retries, 1);
+    assert_eq!(result.write_result.unwrap().next_expected_version, 1);
+
     Ok(())
 }
 
@@ -794,7 +1952,7 @@ async fn wait_for_admin_to_be_available(client: &eventstore::Client) -> eventsto
                 return Ok(());
             }
             Err(e) => match e {
-                eventstore::Error::AccessDenied | eventstore::Error::ServerError(_) => {
+                eventstore::Error::AccessDenied { .. } | eventstore::Error::ServerError { .. } => {
                     tokio::time::sleep(Duration::from_millis(500)).await;
                     debug!("Not available retrying...");
                     continue;
@@ -805,9 +1963,10 @@ async fn wait_for_admin_to_be_available(client: &eventstore::Client) -> eventsto
         };
     }
 
-    Err(eventstore::Error::ServerError(
-        "Waiting for the admin user to be created took too much time".to_string(),
-    ))
+    Err(eventstore::Error::ServerError {
+        code: None,
+        message: "Waiting for the admin user to be created took too much time".to_string(),
+    })
 }
 
 // This function makes sure that we are connected to the leader node but also assumes that we are using
@@ -844,7 +2003,7 @@ async fn wait_for_leader_to_be_elected(client: &eventstore::Client) -> eventstor
             }
 
             // Probably useless considering we made sure to have the admin user ready is by now.
-            if let eventstore::Error::AccessDenied = e {
+            if let eventstore::Error::AccessDenied { .. } = e {
                 tokio::time::sleep(Duration::from_millis(500)).await;
                 debug!("Not available retrying...");
                 continue;
@@ -975,6 +2134,48 @@ async fn all_around_tests(client: Client) -> Result<(), Box<dyn std::error::Erro
     debug!("Before test_write_events…");
     test_write_events(&client).await?;
     debug!("Complete");
+    debug!("Before test_write_events_in_chunks…");
+    test_write_events_in_chunks(&client).await?;
+    debug!("Complete");
+    debug!("Before test_retry_idempotent_operations…");
+    test_retry_idempotent_operations(&client).await?;
+    debug!("Complete");
+    debug!("Before test_list_streams…");
+    test_list_streams(&client).await?;
+    debug!("Complete");
+    debug!("Before test_read_category…");
+    test_read_category(&client).await?;
+    debug!("Complete");
+    debug!("Before test_codec_round_trip…");
+    test_codec_round_trip(&client).await?;
+    debug!("Complete");
+    debug!("Before test_link_events…");
+    test_link_events(&client).await?;
+    debug!("Complete");
+    debug!("Before test_archive_stream…");
+    test_archive_stream(&client).await?;
+    debug!("Complete");
+    debug!("Before test_export_import…");
+    test_export_import(&client).await?;
+    debug!("Complete");
+    debug!("Before test_replication…");
+    test_replication(&client).await?;
+    debug!("Complete");
+    debug!("Before test_replication_resume…");
+    test_replication_resume(&client).await?;
+    debug!("Complete");
+    debug!("Before test_subscription_caught_up…");
+    test_subscription_caught_up(&client).await?;
+    debug!("Complete");
+    debug!("Before test_filtered_subscription_caught_up…");
+    test_filtered_subscription_caught_up(&client).await?;
+    debug!("Complete");
+    debug!("Before test_subscription_dropped_reason…");
+    test_subscription_dropped_reason(&client).await?;
+    debug!("Complete");
+    debug!("Before test_subscription_with_checkpoints…");
+    test_subscription_with_checkpoints(&client).await?;
+    debug!("Complete");
     debug!("Before test_all_read_stream_events…");
     test_read_all_stream_events(&client).await?;
     debug!("Complete");
@@ -987,6 +2188,12 @@ async fn all_around_tests(client: Client) -> Result<(), Box<dyn std::error::Erro
     debug!("Before test test_metadata");
     test_metadata(&client).await?;
     debug!("Complete");
+    debug!("Before test_update_stream_metadata…");
+    test_update_stream_metadata(&client).await?;
+    debug!("Complete");
+    debug!("Before test_system_settings…");
+    test_system_settings(&client).await?;
+    debug!("Complete");
     debug!("Before test test_metadata_not_exist");
     test_metadata_not_exist(&client).await?;
     debug!("Complete");
@@ -996,6 +2203,9 @@ async fn all_around_tests(client: Client) -> Result<(), Box<dyn std::error::Erro
     debug!("Before test_tombstone_stream…");
     test_tombstone_stream(&client).await?;
     debug!("Complete");
+    debug!("Before test_stream_state…");
+    test_stream_state(&client).await?;
+    debug!("Complete");
     debug!("Before test_subscription…");
     test_subscription(&client).await?;
     debug!("Complete");
@@ -1081,6 +2291,15 @@ async fn all_around_tests(client: Client) -> Result<(), Box<dyn std::error::Erro
         }?;
     }
     debug!("Complete");
+    debug!("Before test_read_decide_append");
+    test_read_decide_append(&client).await?;
+    debug!("Complete");
+    debug!("Before test_aggregate_repository");
+    test_aggregate_repository(&client).await?;
+    debug!("Complete");
+    debug!("Before test_aggregate_snapshots");
+    test_aggregate_snapshots(&client).await?;
+    debug!("Complete");
 
     Ok(())
 }